@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 pub struct Buffer<T: Copy> {
     inner: Vec<T>,
     pub size: usize,
@@ -6,44 +8,69 @@ pub struct Buffer<T: Copy> {
 }
 
 impl<T: Copy + std::fmt::Display> Buffer<T> {
-    pub fn new(size: usize) -> Self {
+    pub fn new(inner: Vec<T>) -> Self {
+        let size = inner.len();
         Self {
-            inner: Vec::with_capacity(size),
+            inner,
             size,
             size_f32: size as f32,
             cursor: 0,
         }
     }
 
-    pub fn push(&mut self, value: T) {
-        if self.inner.len() < self.size {
-            self.inner.push(value);
-        } else {
-            let _ = std::mem::replace(&mut self.inner[self.cursor], value);
-        }
-        self.cursor += 1;
+    pub fn fill_to(&mut self, t: f32) {
+        let target = ((t * self.size_f32).ceil().max(0.0) as usize).min(self.size);
+        self.cursor = self.cursor.max(target);
     }
 
-    pub fn as_full_slice(&mut self) -> &[T] {
-        let _ = &mut self
-            .inner
-            .splice(
-                self.cursor..,
-                std::iter::repeat_n(
-                    *self.inner.get(self.cursor - 1).unwrap(),
-                    self.size - self.cursor,
-                ),
-            )
-            .for_each(drop);
+    // Swaps in new contents of the same length, keeping how much is revealed.
+    pub fn replace(&mut self, inner: Vec<T>) {
+        assert_eq!(inner.len(), self.size);
+        self.inner = inner;
+    }
 
+    pub fn as_slice(&self) -> &[T] {
         &self.inner
     }
 
-    pub fn percent_full(&self) -> f32 {
-        (self.cursor as f32) / self.size_f32
+    pub fn visible(&self) -> Range<usize> {
+        0..self.cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_to_reveals_prefix() {
+        let mut buffer = Buffer::new((0..10).collect::<Vec<i32>>());
+        assert_eq!(buffer.size, 10);
+        assert_eq!(buffer.visible(), 0..0);
+
+        buffer.fill_to(0.25);
+        assert_eq!(buffer.visible(), 0..3);
+
+        buffer.fill_to(0.3);
+        assert_eq!(buffer.visible(), 0..3);
+    }
+
+    #[test]
+    fn fill_to_never_shrinks() {
+        let mut buffer = Buffer::new((0..10).collect::<Vec<i32>>());
+        buffer.fill_to(0.5);
+        buffer.fill_to(0.1);
+        assert_eq!(buffer.visible(), 0..5);
     }
 
-    pub fn has_capacity(&self) -> bool {
-        self.cursor < self.size
+    #[test]
+    fn fill_to_is_clamped() {
+        let mut buffer = Buffer::new((0..10).collect::<Vec<i32>>());
+        buffer.fill_to(-1.0);
+        assert_eq!(buffer.visible(), 0..0);
+
+        buffer.fill_to(1.5);
+        assert_eq!(buffer.visible(), 0..10);
+        assert_eq!(buffer.as_slice().len(), 10);
     }
 }
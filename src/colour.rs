@@ -1,9 +1,9 @@
 use std::str::FromStr;
 
+use anyhow::{anyhow, Result};
 use glium::{implement_uniform_block, uniforms::{AsUniformValue, UniformValue}};
-//use anyhow::Result;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Colour {
     pub r: f32,
     pub g: f32,
@@ -21,19 +21,198 @@ impl std::fmt::Display for Colour {
 
 impl FromStr for Colour {
     type Err = anyhow::Error;
-    
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+
+        let colour = if let Some(hex) = s.strip_prefix("#") {
+            Self::from_hex(hex)
+        } else if let Some(args) = function_args(&lower, "rgba").or_else(|| function_args(&lower, "rgb")) {
+            Self::from_rgb_function(args)
+        } else if let Some(args) = function_args(&lower, "hsla").or_else(|| function_args(&lower, "hsl")) {
+            Self::from_hsl_function(args)
+        } else if lower.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            Self::from_name(&lower)
+        } else {
+            Self::from_floats(s)
+        };
+
+        colour.map_err(|e| anyhow!("Unable to parse colour \"{s}\": {e}"))
+    }
+}
+
+impl Colour {
+    pub fn from_rgb(rgb: u32, a: f32) -> Self {
+        Self {
+            r: ((rgb >> 16) & 0xff) as f32 / 255.0,
+            g: ((rgb >> 8) & 0xff) as f32 / 255.0,
+            b: (rgb & 0xff) as f32 / 255.0,
+            a,
+        }
+    }
+
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let h = h.rem_euclid(360.0) / 30.0;
+        let chroma = s * l.min(1.0 - l);
+        let channel = |n: f32| {
+            let k = (n + h) % 12.0;
+            l - chroma * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+
+        Self {
+            r: channel(0.0),
+            g: channel(8.0),
+            b: channel(4.0),
+            a,
+        }
+    }
+
+    fn from_floats(s: &str) -> Result<Self> {
         let val = s
-        .split(",")
-        .map(|x| x.trim().parse::<f32>())
-        .collect::<Result<Vec<f32>, _>>()?;
+            .split(",")
+            .map(|x| unit(x.trim().parse::<f32>()?))
+            .collect::<Result<Vec<f32>>>()?;
+
+        match val[..] {
+            [r, g, b] => Ok(Self { r, g, b, a: 1.0 }),
+            [r, g, b, a] => Ok(Self { r, g, b, a }),
+            _ => Err(anyhow!("expected 3 or 4 components, got {}", val.len())),
+        }
+    }
+
+    fn from_hex(hex: &str) -> Result<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("invalid hex digits"));
+        }
+
+        match hex.len() {
+            6 => Ok(Self::from_rgb(u32::from_str_radix(hex, 16)?, 1.0)),
+            8 => {
+                let rgba = u32::from_str_radix(hex, 16)?;
+                Ok(Self::from_rgb(rgba >> 8, (rgba & 0xff) as f32 / 255.0))
+            }
+            n => Err(anyhow!("expected 6 or 8 hex digits, got {n}")),
+        }
+    }
+
+    fn from_rgb_function(args: &str) -> Result<Self> {
+        let args: Vec<&str> = args.split(",").map(str::trim).collect();
+        let channel = |x: &str| match x.strip_suffix("%") {
+            Some(p) => percentage(p),
+            None => {
+                let v: f32 = x.parse()?;
+                if (0.0..=255.0).contains(&v) {
+                    Ok(v / 255.0)
+                } else {
+                    Err(anyhow!("{v} is outside 0 to 255"))
+                }
+            }
+        };
+
+        let (r, g, b) = match args[..] {
+            [r, g, b] | [r, g, b, _] => (channel(r)?, channel(g)?, channel(b)?),
+            _ => return Err(anyhow!("expected 3 or 4 arguments, got {}", args.len())),
+        };
+        let a = args.get(3).map(|a| alpha(a)).transpose()?.unwrap_or(1.0);
+
+        Ok(Self { r, g, b, a })
+    }
+
+    fn from_hsl_function(args: &str) -> Result<Self> {
+        let args: Vec<&str> = args.split(",").map(str::trim).collect();
+
+        let (h, s, l) = match args[..] {
+            [h, s, l] | [h, s, l, _] => (
+                h.strip_suffix("deg").unwrap_or(h).trim().parse::<f32>()?,
+                percentage(s.strip_suffix("%").unwrap_or(s))?,
+                percentage(l.strip_suffix("%").unwrap_or(l))?,
+            ),
+            _ => return Err(anyhow!("expected 3 or 4 arguments, got {}", args.len())),
+        };
+        let a = args.get(3).map(|a| alpha(a)).transpose()?.unwrap_or(1.0);
+
+        Ok(Self::from_hsl(h, s, l, a))
+    }
+
+    fn from_name(name: &str) -> Result<Self> {
+        if name == "transparent" {
+            return Ok(Self::from_rgb(0, 0.0));
+        }
+
+        NAMED_COLOURS
+            .binary_search_by_key(&name, |(n, _)| n)
+            .map(|i| Self::from_rgb(NAMED_COLOURS[i].1, 1.0))
+            .map_err(|_| anyhow!("unknown colour name"))
+    }
+}
+
+fn function_args<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix("(")?
+        .strip_suffix(")")
+}
+
+fn unit(v: f32) -> Result<f32> {
+    if (0.0..=1.0).contains(&v) {
+        Ok(v)
+    } else {
+        Err(anyhow!("{v} is outside 0 to 1"))
+    }
+}
+
+fn percentage(s: &str) -> Result<f32> {
+    let v: f32 = s.trim().parse()?;
+    if (0.0..=100.0).contains(&v) {
+        Ok(v / 100.0)
+    } else {
+        Err(anyhow!("{v}% is outside 0% to 100%"))
+    }
+}
+
+fn alpha(s: &str) -> Result<f32> {
+    match s.strip_suffix("%") {
+        Some(p) => percentage(p),
+        None => unit(s.parse()?),
+    }
+}
+
+#[derive(Clone)]
+pub enum Gradient {
+    Stops(Vec<Colour>),
+    Rainbow,
+}
+
+impl Gradient {
+    pub fn named(name: &str) -> Option<Self> {
+        let stops = |hex: &[u32]| Gradient::Stops(hex.iter().map(|c| Colour::from_rgb(*c, 1.0)).collect());
+
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rainbow" | "hue" => Some(Gradient::Rainbow),
+            "viridis" => Some(stops(&VIRIDIS)),
+            "magma" => Some(stops(&MAGMA)),
+            _ => None,
+        }
+    }
 
-        Ok(Self {
-            r: val[0],
-            g: val[1],
-            b: val[2],
-            a: val[3],
-        })
+    // Colours sampled evenly along the gradient, ready to be uploaded as a
+    // linearly filtered texture.
+    pub fn texels(&self) -> Vec<(f32, f32, f32, f32)> {
+        let colours = match self {
+            Gradient::Stops(stops) => stops.clone(),
+            Gradient::Rainbow => (0..12)
+                .map(|i| Colour::from_hsl(i as f32 * 30.0, 1.0, 0.5, 1.0))
+                .collect(),
+        };
+
+        colours.into_iter().map(|c| (c.r, c.g, c.b, c.a)).collect()
+    }
+
+    // A cyclic gradient wraps around seamlessly, while stops bounce back from
+    // the last colour to the first.
+    pub fn is_cyclic(&self) -> bool {
+        matches!(self, Gradient::Rainbow)
     }
 }
 
@@ -47,4 +226,238 @@ impl AsUniformValue for Colour {
     fn as_uniform_value(&self) -> glium::uniforms::UniformValue<'_> {
         UniformValue::Vec4(<[f32; 4]>::from(*self))
     }
-}
\ No newline at end of file
+}
+
+// Matplotlib colourmaps sampled at ten evenly spaced points.
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3e4989, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6ece58, 0xb5de2b, 0xfde725,
+];
+const MAGMA: [u32; 10] = [
+    0x000004, 0x180f3d, 0x440f76, 0x721f81, 0x9e2f7f, 0xcd4071, 0xf1605d, 0xfd9668, 0xfeca8d, 0xfcfdbf,
+];
+
+// CSS named colours, sorted by name for binary search.
+const NAMED_COLOURS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> [f32; 4] {
+        s.parse::<Colour>().unwrap().into()
+    }
+
+    #[test]
+    fn parses_rgba() {
+        let colour: Colour = " 1.0, 0.5,0.25 , 0 ".parse().unwrap();
+        assert_eq!(<[f32; 4]>::from(colour), [1.0, 0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn parses_rgb_with_default_alpha() {
+        assert_eq!(parse("1, 0, 0"), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(parse("#ff0000"), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(parse("#00FF0080"), [0.0, 1.0, 0.0, 128.0 / 255.0]);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(parse("red"), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(parse("CornflowerBlue"), <[f32; 4]>::from(Colour::from_rgb(0x6495ed, 1.0)));
+        assert_eq!(parse("transparent")[3], 0.0);
+        assert!(NAMED_COLOURS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn parses_rgb_function() {
+        assert_eq!(parse("rgb(255, 0, 0)"), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(parse("rgba(0, 255, 0, 0.5)"), [0.0, 1.0, 0.0, 0.5]);
+        assert_eq!(parse("RGB(0%, 0%, 100%, 50%)"), [0.0, 0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn parses_hsl_function() {
+        assert_eq!(parse("hsl(0, 100%, 50%)"), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(parse("hsl(120deg, 100%, 50%)"), [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(parse("hsla(240, 100%, 50%, 0.25)"), [0.0, 0.0, 1.0, 0.25]);
+        assert_eq!(parse("hsl(0, 0%, 100%)"), [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert!("1.0, red, 0.0, 1.0".parse::<Colour>().is_err());
+        assert!("".parse::<Colour>().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_colours() {
+        for s in [
+            "1, 0",
+            "1, 0, 0, 1, 0",
+            "2, 0, 0",
+            "#ff00",
+            "#gg0000",
+            "rgb(256, 0, 0)",
+            "rgb(255, 0)",
+            "rgba(255, 0, 0, 2)",
+            "hsl(0, 150%, 50%)",
+            "notacolour",
+            "rgb(255, 0, 0",
+        ] {
+            assert!(s.parse::<Colour>().is_err(), "{s} should not parse");
+        }
+    }
+}
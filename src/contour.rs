@@ -0,0 +1,341 @@
+use std::{collections::BTreeMap, fs::File, io::BufReader, path::Path};
+
+use anyhow::{anyhow, Result};
+
+use crate::{fourier::Route, Complex32};
+
+// A binarised image, row by row from the top.
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    filled: Vec<bool>,
+}
+
+impl Bitmap {
+    // Pixels darker than `threshold` are filled, or lighter ones if `invert`
+    // is set. Transparent pixels count as white.
+    pub fn load(path: &Path, threshold: f32, invert: bool) -> Result<Self> {
+        let file = File::open(path).map_err(|e| anyhow!("Unable to open {}: {e}", path.display()))?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| anyhow!("Unable to read {}: {e}", path.display()))?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data)?;
+
+        let filled = data[..info.buffer_size()]
+            .chunks_exact(info.color_type.samples())
+            .map(|pixel| {
+                let (luma, alpha) = match *pixel {
+                    [l] => (l as f32, 255),
+                    [l, a] => (l as f32, a),
+                    [r, g, b] => (luma(r, g, b), 255),
+                    [r, g, b, a, ..] => (luma(r, g, b), a),
+                    [] => unreachable!(),
+                };
+                let alpha = alpha as f32 / 255.0;
+                let brightness = luma / 255.0 * alpha + 1.0 - alpha;
+                (brightness < threshold) != invert
+            })
+            .collect();
+
+        Ok(Self {
+            width: info.width as usize,
+            height: info.height as usize,
+            filled,
+        })
+    }
+
+    // Everything outside the image is empty, so every contour closes.
+    fn filled(&self, (x, y): (i32, i32)) -> bool {
+        (0..self.width as i32).contains(&x)
+            && (0..self.height as i32).contains(&y)
+            && self.filled[y as usize * self.width + x as usize]
+    }
+
+    // Closed outlines between filled and empty pixels by marching squares,
+    // largest first. Points are in pixels with y up and the origin at the
+    // centre of the image.
+    pub fn contours(&self) -> Vec<Vec<Complex32>> {
+        // Crossings sit halfway along cell edges, so they're keyed by twice
+        // their position. Each one maps to the next crossing along its contour.
+        let mut next = BTreeMap::new();
+
+        for y in -1..self.height as i32 {
+            for x in -1..self.width as i32 {
+                // Clockwise on screen from the top left.
+                let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+                let crossings: Vec<((i32, i32), bool)> = (0..4)
+                    .filter_map(|i| {
+                        let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                        let leaving = self.filled(a);
+                        (leaving != self.filled(b)).then_some(((a.0 + b.0, a.1 + b.1), leaving))
+                    })
+                    .collect();
+
+                // Crossings alternate between leaving and entering the filled
+                // area. Pairing each exit with the entry before it keeps
+                // diagonal pixels of a saddle apart.
+                for (i, &(point, leaving)) in crossings.iter().enumerate() {
+                    if leaving {
+                        next.insert(point, crossings[(i + crossings.len() - 1) % crossings.len()].0);
+                    }
+                }
+            }
+        }
+
+        let (cx, cy) = ((self.width as f32 - 1.0) / 2.0, (self.height as f32 - 1.0) / 2.0);
+        let mut contours = vec![];
+        while let Some((&start, _)) = next.first_key_value() {
+            let mut contour = vec![];
+            let mut point = start;
+            while let Some(following) = next.remove(&point) {
+                contour.push(Complex32::new(point.0 as f32 / 2.0 - cx, cy - point.1 as f32 / 2.0));
+                point = following;
+            }
+            contours.push(contour);
+        }
+
+        contours.sort_by(|a, b| area(b).total_cmp(&area(a)));
+        contours
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> f32 {
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+}
+
+fn area(contour: &[Complex32]) -> f32 {
+    let doubled: f32 = contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .map(|(a, b)| a.re * b.im - b.re * a.im)
+        .sum();
+    doubled.abs() / 2.0
+}
+
+// Douglas-Peucker on a closed contour, which is split at its first point and
+// the point furthest from it.
+pub fn simplify(contour: &[Complex32], tolerance: f32) -> Vec<Complex32> {
+    let Some(first) = contour.first() else {
+        return vec![];
+    };
+    let far = (0..contour.len())
+        .max_by(|a, b| (contour[*a] - first).norm().total_cmp(&(contour[*b] - first).norm()))
+        .unwrap();
+    if far == 0 {
+        return contour.to_vec();
+    }
+
+    let mut points = douglas_peucker(&contour[..=far], tolerance);
+    let mut back: Vec<_> = contour[far..].to_vec();
+    back.push(*first);
+    points.pop();
+    points.extend(douglas_peucker(&back, tolerance));
+    points.pop();
+    points
+}
+
+fn douglas_peucker(points: &[Complex32], tolerance: f32) -> Vec<Complex32> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let furthest = (1..points.len().saturating_sub(1))
+        .map(|i| (i, segment_distance(points[i], first, last)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    match furthest {
+        Some((i, distance)) if distance > tolerance => {
+            let mut simplified = douglas_peucker(&points[..=i], tolerance);
+            simplified.pop();
+            simplified.extend(douglas_peucker(&points[i..], tolerance));
+            simplified
+        }
+        _ => vec![first, last],
+    }
+}
+
+fn segment_distance(p: Complex32, a: Complex32, b: Complex32) -> f32 {
+    let ab = b - a;
+    let length = ab.norm_sqr();
+    if length == 0.0 {
+        return (p - a).norm();
+    }
+    let s = ((p - a).re * ab.re + (p - a).im * ab.im) / length;
+    (p - (a + ab * s.clamp(0.0, 1.0))).norm()
+}
+
+// Joins closed contours into one closed route. Contours are visited nearest
+// first, each entered at its point closest to where the last one was left and
+// left there again after a full loop, with hidden travel in between.
+pub fn join(contours: &[Vec<Complex32>]) -> Route {
+    let mut remaining: Vec<&Vec<Complex32>> = contours.iter().filter(|c| !c.is_empty()).collect();
+    if remaining.is_empty() {
+        return Route::from(vec![]);
+    }
+
+    let mut order = vec![remaining.remove(0).clone()];
+    while !remaining.is_empty() {
+        let exit = order[order.len() - 1][0];
+        let (index, (entry, _)) = remaining
+            .iter()
+            .map(|contour| nearest(contour, exit))
+            .enumerate()
+            .min_by(|a, b| a.1 .1.total_cmp(&b.1 .1))
+            .unwrap();
+        let contour = remaining.remove(index);
+        order.push(contour[entry..].iter().chain(&contour[..entry]).copied().collect());
+    }
+
+    // Each contour is closed by repeating its first point, and the edge from
+    // there to the next contour, or back to the start, is travel.
+    let mut points = vec![];
+    let mut travel = vec![];
+    let mut distance = 0.0;
+    for (i, contour) in order.iter().enumerate() {
+        points.extend(contour.iter().chain(&contour[..1]));
+        distance += contour
+            .iter()
+            .zip(contour.iter().cycle().skip(1))
+            .map(|(a, b)| (b - a).norm())
+            .sum::<f32>();
+
+        let gap = (order[(i + 1) % order.len()][0] - contour[0]).norm();
+        travel.push(distance..distance + gap);
+        distance += gap;
+    }
+
+    Route {
+        points,
+        hidden: travel.into_iter().map(|range| range.start / distance..range.end / distance).collect(),
+    }
+}
+
+fn nearest(contour: &[Complex32], p: Complex32) -> (usize, f32) {
+    contour
+        .iter()
+        .enumerate()
+        .map(|(i, q)| (i, (q - p).norm()))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+}
+
+// `count` points evenly spaced by arc length around a closed path.
+pub fn resample(path: &[Complex32], count: usize) -> Vec<Complex32> {
+    let edges: Vec<(Complex32, Complex32)> = path.iter().copied().zip(path.iter().copied().cycle().skip(1)).collect();
+    let perimeter: f32 = edges.iter().map(|(a, b)| (b - a).norm()).sum();
+    if perimeter == 0.0 {
+        return path.to_vec();
+    }
+
+    let mut points = Vec::with_capacity(count);
+    let mut edges = edges.into_iter();
+    let (mut a, mut b) = edges.next().unwrap();
+    let mut start = 0.0;
+
+    for i in 0..count {
+        let distance = perimeter * i as f32 / count as f32;
+        while start + (b - a).norm() < distance {
+            start += (b - a).norm();
+            match edges.next() {
+                Some(edge) => (a, b) = edge,
+                None => break,
+            }
+        }
+        let length = (b - a).norm();
+        let s = if length > 0.0 { (distance - start) / length } else { 0.0 };
+        points.push(a + (b - a) * s.min(1.0));
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(rows: &[&str]) -> Bitmap {
+        Bitmap {
+            width: rows[0].len(),
+            height: rows.len(),
+            filled: rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect(),
+        }
+    }
+
+    #[test]
+    fn contours_are_closed_and_largest_first() {
+        let contours = bitmap(&[
+            "......",
+            ".###..",
+            ".#.#..",
+            ".###.#",
+            "......",
+        ])
+        .contours();
+
+        // The ring's outside and its hole, then the single pixel.
+        assert_eq!(contours.len(), 3);
+        assert_eq!(area(&contours[0]), 8.5);
+        assert_eq!(area(&contours[1]), 0.5);
+        assert_eq!(area(&contours[2]), 0.5);
+        assert_eq!(contours[2].len(), 4);
+
+        // Every step crosses a single cell, and the last leads back to the start.
+        for contour in &contours {
+            for (a, b) in contour.iter().zip(contour.iter().cycle().skip(1)) {
+                assert!((b - a).norm() <= 1.0, "{a} to {b}");
+            }
+        }
+
+        // The origin sits at the centre of the image, y up.
+        let top = contours[0].iter().map(|p| p.im).fold(f32::MIN, f32::max);
+        assert_eq!(top, 1.5);
+    }
+
+    #[test]
+    fn diagonal_pixels_stay_apart() {
+        assert_eq!(bitmap(&["#.", ".#"]).contours().len(), 2);
+    }
+
+    #[test]
+    fn simplify_keeps_corners() {
+        let square: Vec<_> = (0..4)
+            .flat_map(|side| {
+                let corner = Complex32::i().powi(side) * Complex32::new(1.0, -1.0);
+                let direction = Complex32::i().powi(side) * Complex32::new(0.0, 0.5);
+                (0..4).map(move |i| corner + direction * i as f32)
+            })
+            .collect();
+
+        let simplified = simplify(&square, 0.01);
+        assert_eq!(simplified.len(), 4);
+        assert!(simplified.iter().all(|p| (p.re.abs() - 1.0).abs() < 1e-6 && (p.im.abs() - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn join_visits_the_nearest_contour_next() {
+        let square = |x: f32| -> Vec<Complex32> {
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(re, im)| Complex32::new(x + re, im)).to_vec()
+        };
+        let route = join(&[square(0.0), square(10.0), square(3.0)]);
+
+        // The middle square comes second, entered at its corner nearest the first.
+        assert_eq!(route.points.len(), 15);
+        assert_eq!(route.points[4], Complex32::new(0.0, 0.0));
+        assert_eq!(route.points[5], Complex32::new(3.0, 0.0));
+        assert_eq!(route.points[10], Complex32::new(10.0, 0.0));
+
+        // Perimeters of 4 each, with travel of 3 and 7 out and 10 back.
+        let expected = [4.0..7.0, 11.0..18.0, 22.0..32.0].map(|r| r.start / 32.0..r.end / 32.0);
+        assert_eq!(route.hidden, expected);
+    }
+
+    #[test]
+    fn resample_spaces_points_by_arc_length() {
+        let path = [Complex32::new(0.0, 0.0), Complex32::new(3.0, 0.0), Complex32::new(3.0, 1.0), Complex32::new(0.0, 1.0)];
+        let points = resample(&path, 8);
+        assert_eq!(points.len(), 8);
+        assert_eq!(points[1], Complex32::new(1.0, 0.0));
+        assert_eq!(points[3], Complex32::new(3.0, 0.0));
+        assert_eq!(points[4], Complex32::new(3.0, 1.0));
+        assert_eq!(points[7], Complex32::new(0.0, 1.0));
+    }
+}
@@ -0,0 +1,377 @@
+use std::f64::consts::{E, PI, TAU};
+
+use anyhow::{anyhow, Result};
+use num::complex::Complex64;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(usize, usize),
+    Operator(char),
+    Open,
+    Close,
+    End,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Sqrt,
+    Exp,
+    Ln,
+    Abs,
+    Arg,
+    Re,
+    Im,
+    Conj,
+    Floor,
+    Sign,
+}
+
+const FUNCTIONS: [(&str, Function); 20] = [
+    ("sin", Function::Sin),
+    ("cos", Function::Cos),
+    ("tan", Function::Tan),
+    ("asin", Function::Asin),
+    ("acos", Function::Acos),
+    ("atan", Function::Atan),
+    ("sinh", Function::Sinh),
+    ("cosh", Function::Cosh),
+    ("tanh", Function::Tanh),
+    ("sqrt", Function::Sqrt),
+    ("exp", Function::Exp),
+    ("ln", Function::Ln),
+    ("log", Function::Ln),
+    ("abs", Function::Abs),
+    ("arg", Function::Arg),
+    ("re", Function::Re),
+    ("im", Function::Im),
+    ("conj", Function::Conj),
+    ("floor", Function::Floor),
+    ("sign", Function::Sign),
+];
+
+const CONSTANTS: [(&str, Complex64); 4] = [
+    ("pi", Complex64::new(PI, 0.0)),
+    ("tau", Complex64::new(TAU, 0.0)),
+    ("e", Complex64::new(E, 0.0)),
+    ("i", Complex64::new(0.0, 1.0)),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(Complex64),
+    Parameter,
+    Negate(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+    Call(Function, Box<Node>),
+}
+
+// A complex function of the curve parameter `t`, such as `exp(i*t) + 0.5*sin(3*t)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    root: Node,
+}
+
+impl std::str::FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            source: s,
+            tokens: tokenise(s)?,
+            next: 0,
+        };
+        let root = parser.sum()?;
+        match parser.peek() {
+            (Token::End, _) => Ok(Self { root }),
+            (_, position) => Err(parser.error("Unexpected token", position)),
+        }
+    }
+}
+
+impl Expression {
+    pub fn eval(&self, t: f64) -> Complex64 {
+        eval(&self.root, t)
+    }
+}
+
+fn eval(node: &Node, t: f64) -> Complex64 {
+    match node {
+        Node::Number(x) => *x,
+        Node::Parameter => Complex64::new(t, 0.0),
+        // Subtracting keeps the zero imaginary part of a negated real positive,
+        // so that sqrt(-4) lands on the right side of the branch cut.
+        Node::Negate(x) => Complex64::new(0.0, 0.0) - eval(x, t),
+        Node::Binary(op, a, b) => {
+            let (a, b) = (eval(a, t), eval(b, t));
+            match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                '/' => a / b,
+                _ => power(a, b),
+            }
+        }
+        Node::Call(function, x) => {
+            let x = eval(x, t);
+            match function {
+                Function::Sin => x.sin(),
+                Function::Cos => x.cos(),
+                Function::Tan => x.tan(),
+                Function::Asin => x.asin(),
+                Function::Acos => x.acos(),
+                Function::Atan => x.atan(),
+                Function::Sinh => x.sinh(),
+                Function::Cosh => x.cosh(),
+                Function::Tanh => x.tanh(),
+                Function::Sqrt => x.sqrt(),
+                Function::Exp => x.exp(),
+                Function::Ln => x.ln(),
+                Function::Abs => Complex64::new(x.norm(), 0.0),
+                Function::Arg => Complex64::new(x.arg(), 0.0),
+                Function::Re => Complex64::new(x.re, 0.0),
+                Function::Im => Complex64::new(x.im, 0.0),
+                Function::Conj => x.conj(),
+                Function::Floor => Complex64::new(x.re.floor(), x.im.floor()),
+                Function::Sign if x.norm() == 0.0 => x,
+                Function::Sign => x / x.norm(),
+            }
+        }
+    }
+}
+
+// Keeps real and integer powers exact, which `powc` would leave with rounding
+// in the imaginary part or turn into NaN at zero.
+fn power(a: Complex64, b: Complex64) -> Complex64 {
+    match b.im == 0.0 {
+        true if b.re.fract() == 0.0 && b.re.abs() <= i32::MAX as f64 => a.powi(b.re as i32),
+        true if a.im == 0.0 && a.re >= 0.0 => Complex64::new(a.re.powf(b.re), 0.0),
+        _ => a.powc(b),
+    }
+}
+
+// An error with the expression quoted and a caret under `position`.
+fn located(message: impl std::fmt::Display, source: &str, position: usize) -> anyhow::Error {
+    let column = source[..position].chars().count();
+    anyhow!("{message} at position {column}:\n    {source}\n    {}^", " ".repeat(column))
+}
+
+// Tokens paired with their byte offset in the source, for error messages.
+fn tokenise(s: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '0'..='9' | '.' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    let exponent_sign = matches!(c, '+' | '-') && s[..i].ends_with(['e', 'E']);
+                    if !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E') || exponent_sign) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let number = s[start..end]
+                    .parse()
+                    .map_err(|_| located(format!("Invalid number {}", &s[start..end]), s, start))?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                Token::Name(start, end)
+            }
+            '+' | '-' | '*' | '/' | '^' => {
+                chars.next();
+                Token::Operator(c)
+            }
+            '(' => {
+                chars.next();
+                Token::Open
+            }
+            ')' => {
+                chars.next();
+                Token::Close
+            }
+            other => return Err(located(format!("Unexpected {other}"), s, start)),
+        };
+        tokens.push((token, start));
+    }
+
+    tokens.push((Token::End, s.len()));
+    Ok(tokens)
+}
+
+// Recursive descent with the usual precedence: sums, then products, then
+// unary minus, then right associative powers.
+struct Parser<'s> {
+    source: &'s str,
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> (Token, usize) {
+        self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.next];
+        self.next = (self.next + 1).min(self.tokens.len() - 1);
+        token
+    }
+
+    fn error(&self, what: &str, position: usize) -> anyhow::Error {
+        match position < self.source.len() {
+            true => located(what, self.source, position),
+            false => located("Unexpected end of expression", self.source, position),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Node> {
+        let mut node = self.product()?;
+        while let (Token::Operator(op @ ('+' | '-')), _) = self.peek() {
+            self.advance();
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+        Ok(node)
+    }
+
+    fn product(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+        while let (Token::Operator(op @ ('*' | '/')), _) = self.peek() {
+            self.advance();
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.peek() {
+            (Token::Operator('-'), _) => {
+                self.advance();
+                Ok(Node::Negate(Box::new(self.unary()?)))
+            }
+            (Token::Operator('+'), _) => {
+                self.advance();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Node> {
+        let base = self.primary()?;
+        match self.peek() {
+            (Token::Operator('^'), _) => {
+                self.advance();
+                Ok(Node::Binary('^', Box::new(base), Box::new(self.unary()?)))
+            }
+            _ => Ok(base),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.advance() {
+            (Token::Number(x), _) => Ok(Node::Number(Complex64::new(x, 0.0))),
+            (Token::Open, _) => {
+                let node = self.sum()?;
+                match self.advance() {
+                    (Token::Close, _) => Ok(node),
+                    (_, position) => Err(self.error("Expected )", position)),
+                }
+            }
+            (Token::Name(start, end), position) => {
+                let name = &self.source[start..end];
+                if let Some((_, function)) = FUNCTIONS.iter().find(|(n, _)| *n == name) {
+                    return match self.peek() {
+                        (Token::Open, _) => Ok(Node::Call(*function, Box::new(self.primary()?))),
+                        (_, position) => Err(self.error(&format!("Expected ( after {name}"), position)),
+                    };
+                }
+                if let Some((_, value)) = CONSTANTS.iter().find(|(n, _)| *n == name) {
+                    return Ok(Node::Number(*value));
+                }
+                match name {
+                    "t" => Ok(Node::Parameter),
+                    _ => Err(located(format!("Unknown name {name}"), self.source, position)),
+                }
+            }
+            (_, position) => Err(self.error("Unexpected token", position)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(s: &str, t: f64) -> Complex64 {
+        s.parse::<Expression>().unwrap().eval(t)
+    }
+
+    fn real(s: &str, t: f64) -> f64 {
+        let z = eval(s, t);
+        assert_eq!(z.im, 0.0, "{s}");
+        z.re
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(real("1 + 2 * 3", 0.0), 7.0);
+        assert_eq!(real("(1 + 2) * 3", 0.0), 9.0);
+        assert_eq!(real("2 ^ 3 ^ 2", 0.0), 512.0);
+        assert_eq!(real("-2 ^ 2", 0.0), -4.0);
+        assert_eq!(real("(-2) ^ 3", 0.0), -8.0);
+        assert_eq!(real("8 / 4 / 2", 0.0), 1.0);
+        assert_eq!(real("1.5e1 - t", 5.0), 10.0);
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert!((real("cos(3*t) + 0.5*sin(t)", PI / 2.0) - 0.5).abs() < 1e-12);
+        assert!((real("exp(ln(2)) * tau / pi", 0.0) - 4.0).abs() < 1e-12);
+        assert_eq!(real("abs(-3) + floor(2.7) + sign(-t)", 1.0), 4.0);
+    }
+
+    #[test]
+    fn complex_values() {
+        assert_eq!(eval("i^2", 0.0), Complex64::new(-1.0, 0.0));
+        assert_eq!(eval("(1 + 2*i) * conj(1 + 2*i)", 0.0), Complex64::new(5.0, 0.0));
+        assert!((eval("exp(i*t)", PI / 2.0) - Complex64::new(0.0, 1.0)).norm() < 1e-12);
+        assert!((eval("sqrt(-4)", 0.0) - Complex64::new(0.0, 2.0)).norm() < 1e-12);
+        assert_eq!(real("abs(3 + 4*i) + re(i) + im(2*i)", 0.0), 7.0);
+    }
+
+    #[test]
+    fn errors_point_to_the_position() {
+        let error = |s: &str| s.parse::<Expression>().unwrap_err().to_string();
+
+        assert_eq!(error("1 + * 2"), "Unexpected token at position 4:\n    1 + * 2\n        ^");
+        assert_eq!(error("sin t"), "Expected ( after sin at position 4:\n    sin t\n        ^");
+        assert_eq!(error("2 * x"), "Unknown name x at position 4:\n    2 * x\n        ^");
+        assert_eq!(error("(1 + 2"), "Unexpected end of expression at position 6:\n    (1 + 2\n          ^");
+        assert_eq!(error("π $ 2"), "Unexpected $ at position 2:\n    π $ 2\n      ^");
+    }
+}
@@ -1,91 +1,468 @@
-use glium::{glutin::surface::WindowSurface, Frame};
+use glium::{backend::Facade, draw_parameters::Smooth, Surface};
 
-use std::{f32::consts::PI, sync::Arc};
+use std::{f32::consts::PI, f64::consts::PI as PI64, ops::Range, rc::Rc};
+
+use num::complex::Complex64;
+use rustfft::FftPlanner;
 
 use crate::{
-    bar_vertex::Bar, buffer::Buffer, graphics::Drawable, outline_vertex::Outline, BarVertex, Colour, Complex32, OutlineVertex
+    buffer::Buffer, colour::Gradient, gpu_bar::GpuBar, graphics::{aspect_scale, Drawable}, markers::Markers, polyline::{LineStyle, Polyline}, spectrum::Spectrum, traces::{ProjectionTraces, Regions}, Colour, Complex32, LineVertex
 };
 
+// Incremental rotation slowly drifts away from the unit circle, so the
+// phasors are snapped back to their true magnitude every this many frames.
+const RENORM_INTERVAL: u32 = 64;
+
 pub struct FourierSet<'a> {
-    pub outline_gpu: Outline<'a>,
-    pub outline_buffer: Buffer<OutlineVertex>,
+    pub name: String,
+    pub outline_gpu: Polyline<'a>,
+    pub outline_buffer: Buffer<LineVertex>,
+    pub ghosts: Vec<Ghost<'a>>,
+    gradient_speed: f32,
+    samples: usize,
+    hidden: Vec<Range<f32>>,
+
+    // The highest harmonic in the drawn chain and outline, and the range it
+    // grows through over each period when animated.
+    cutoff: u32,
+    cutoff_animation: Option<(u32, u32)>,
 
     pub bars: Vec<(i32, Complex32)>,
-    pub bar_gpu: Bar<'a>,
+    bar_params: Vec<f32>,
+    bar_distances: Vec<f32>,
+    pub bar_gpu: BarRenderer<'a>,
+    markers: Option<Markers<'a>>,
+    spectrum: Option<Spectrum<'a>>,
+    traces: Option<ProjectionTraces<'a>>,
+    phasors: Phasors,
+}
+
+pub struct Ghost<'a> {
+    pub outline_gpu: Polyline<'a>,
+    pub outline_buffer: Buffer<LineVertex>,
+}
+
+#[derive(Clone, Copy)]
+pub enum BarShading {
+    Frequency,
+    Rotation,
+    Amplitude,
+}
+
+// Points traced over one period, with the fractions of the period spent
+// travelling between contours, which aren't drawn.
+#[derive(Debug)]
+pub struct Route {
+    pub points: Vec<Complex32>,
+    pub hidden: Vec<Range<f32>>,
+}
+
+impl From<Vec<Complex32>> for Route {
+    fn from(points: Vec<Complex32>) -> Self {
+        Self { points, hidden: vec![] }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+pub enum BarRenderer<'a> {
+    Cpu(Polyline<'a>),
+    Gpu(GpuBar<'a>),
 }
 
 impl<'a> FourierSet<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        points: &mut [Complex32],
+        name: String,
+        route: &Route,
         samples: usize,
+        step: f32,
 
-        outline_colour: Colour,
-        _outline_width: f32, //todo: Implement
-        outline_program: Arc<glium::Program>,
+        outline_style: LineStyle,
+        outline_program: Rc<glium::Program>,
+        outline_gradient: Option<Gradient>,
+        outline_gradient_speed: f32,
+        ghosts: &[(usize, Colour)],
 
-        bar_colour: Colour,
-        _bar_width: f32,
-        bar_program: Arc<glium::Program>,
+        bar_style: LineStyle,
+        bar_program: Rc<glium::Program>,
+        bar_colourmap: Option<(Gradient, BarShading)>,
+        gpu_bar_program: Option<Rc<glium::Program>>,
 
-        facade: &glium::Display<WindowSurface>,
+        facade: &impl Facade,
     ) -> Self {
-        let n = points.len();
-        normalise(points);
-        let bars = fourier_transform(&points);
+        let n = route.points.len();
+        let bars = fourier_transform(&route.points);
+
+        let (bar_params, bar_style, bar_colourmap) = match bar_colourmap {
+            Some((colourmap, shading)) => (
+                bar_params(&bars, shading),
+                LineStyle {
+                    colour: Colour { r: 1.0, g: 1.0, b: 1.0, a: bar_style.colour.a },
+                    ..bar_style
+                },
+                Some(colourmap),
+            ),
+            None => (vec![0.0; bars.len()], bar_style, None),
+        };
+
+        let bar_gpu = match gpu_bar_program {
+            Some(program) => BarRenderer::Gpu(GpuBar::new(facade, &bars, &bar_params, program, &bar_style, bar_colourmap.as_ref())),
+            None => BarRenderer::Cpu(Polyline::dynamic("Bars", facade, n, bar_program, &bar_style, bar_colourmap.as_ref())),
+        };
+
+        let outline_buffer = Buffer::new(outline_vertices(inverse_transform(&bars, samples), &route.hidden));
+
+        let ghosts = ghosts
+            .iter()
+            .map(|(harmonics, colour)| {
+                // Bars are ordered 0, 1, -1, 2, -2, ..., so the first 2k + 1
+                // of them are exactly the partial sum up to harmonic k.
+                let cutoff = (2 * harmonics + 1).min(bars.len());
+                let buffer = Buffer::new(outline_vertices(inverse_transform(&bars[..cutoff], samples), &route.hidden));
+
+                let style = LineStyle { colour: *colour, ..outline_style };
+
+                Ghost {
+                    outline_gpu: Polyline::new("Ghost", facade, buffer.as_slice(), Rc::clone(&outline_program), &style, None),
+                    outline_buffer: buffer,
+                }
+            })
+            .collect();
+
+        // A gradient supplies its own colours, so the outline colour no longer tints it.
+        let outline_style = match outline_gradient {
+            Some(_) => LineStyle {
+                colour: Colour { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+                ..outline_style
+            },
+            None => outline_style,
+        };
 
         Self {
-            outline_gpu: Outline::new(facade, samples, outline_program, outline_colour),
-            outline_buffer: Buffer::new(samples),
+            name,
+            outline_gpu: Polyline::new("Outline", facade, outline_buffer.as_slice(), outline_program, &outline_style, outline_gradient.as_ref()),
+            outline_buffer,
+            ghosts,
+            gradient_speed: outline_gradient_speed,
+            samples,
+            hidden: route.hidden.clone(),
+            cutoff: max_harmonic(&bars),
+            cutoff_animation: None,
+            phasors: Phasors::new(&bars, step),
+            bar_distances: bar_distances(&bars),
             bars,
-            bar_gpu: Bar::new(facade, n, bar_program, bar_colour),
+            bar_params,
+            bar_gpu,
+            markers: None,
+            spectrum: None,
+            traces: None,
+        }
+    }
+
+    // The highest harmonic there is, which is what ghost cutoffs are measured in.
+    pub fn harmonics(&self) -> u32 {
+        max_harmonic(&self.bars)
+    }
+
+    // Adds one harmonic at a time to the chain, from `from` at the start of
+    // each period to `to` at the end.
+    pub fn set_cutoff_animation(&mut self, from: u32, to: u32) {
+        let to = to.min(self.harmonics());
+        let from = from.min(to);
+        self.cutoff_animation = Some((from, to));
+        self.set_cutoff(from);
+    }
+
+    // Rebuilds the outline from the harmonics up to `cutoff`, so that the
+    // pen stays on it. How much of it is revealed stays the same, but the
+    // trail already drawn is redrawn as the new partial sum too: keeping the
+    // old trail would leave a jump where it meets the new one.
+    fn set_cutoff(&mut self, cutoff: u32) {
+        if cutoff == self.cutoff {
+            return;
+        }
+        self.cutoff = cutoff;
+
+        let outline = inverse_transform(&self.bars[..self.drawn_bars()], self.samples);
+        self.outline_buffer.replace(outline_vertices(outline, &self.hidden));
+        self.outline_gpu.upload(self.outline_buffer.as_slice());
+    }
+
+    // Bars are ordered 0, 1, -1, 2, -2, ..., so the chain up to harmonic k
+    // is the first 2k + 1 of them.
+    fn drawn_bars(&self) -> usize {
+        (2 * self.cutoff as usize + 1).min(self.bars.len())
+    }
+
+    pub fn set_markers(&mut self, markers: Markers<'a>) {
+        self.markers = Some(markers);
+    }
+
+    pub fn set_spectrum(&mut self, spectrum: Spectrum<'a>) {
+        self.spectrum = Some(spectrum);
+    }
+
+    pub fn set_traces(&mut self, traces: ProjectionTraces<'a>) {
+        self.traces = Some(traces);
+    }
+
+    pub fn set_line_hints(&mut self, smooth: Option<Smooth>, width_scale: f32) {
+        if let Some(traces) = &mut self.traces {
+            traces.set_line_hints(smooth, width_scale);
         }
+        if let Some(markers) = &mut self.markers {
+            markers.set_size_scale(width_scale);
+        }
+        match &mut self.bar_gpu {
+            BarRenderer::Cpu(bar) => bar.set_line_hints(smooth, width_scale),
+            BarRenderer::Gpu(bar) => bar.set_line_hints(smooth, width_scale),
+        }
+        for ghost in &mut self.ghosts {
+            ghost.outline_gpu.set_line_hints(smooth, width_scale);
+        }
+        self.outline_gpu.set_line_hints(smooth, width_scale);
     }
 
-    pub fn draw(&mut self, facade: &mut Frame, t: f32) {
-        self.bar_gpu.upload(&self.get_bar_pos(t));
-        self.bar_gpu.draw(facade).unwrap();
-        
-        while self.outline_buffer.has_capacity() && (self.outline_buffer.percent_full() < t) {
-            self.calc_next_bar_pos();
+    // Advances the animation to `t`. Drawing is separate so that one frame
+    // can be drawn into several surfaces without recomputing it.
+    pub fn update(&mut self, t: f32) {
+        if let Some((from, to)) = self.cutoff_animation {
+            let steps = to - from + 1;
+            self.set_cutoff(from + ((t.clamp(0.0, 1.0) * steps as f32) as u32).min(steps - 1));
+        }
+        let drawn = self.drawn_bars();
+
+        // The GPU renderer computes its own positions, but markers still need them here.
+        let positions: Vec<Complex32> = match (&self.bar_gpu, &self.markers) {
+            (BarRenderer::Gpu(_), None) => vec![],
+            _ => {
+                self.phasors.seek(&self.bars, t);
+                get_bar_pos_iter(&self.phasors.current).collect()
+            }
         };
 
-        let slice = self.outline_buffer.as_full_slice();
-        
-        self.outline_gpu.upload(slice);
-        self.outline_gpu.draw(facade).unwrap();
+        match &mut self.bar_gpu {
+            BarRenderer::Cpu(bar) => {
+                let pos: Vec<LineVertex> = positions
+                    .iter()
+                    .zip(&self.bar_params)
+                    .zip(&self.bar_distances)
+                    .map(|((c, param), distance)| LineVertex {
+                        param: *param,
+                        distance: *distance,
+                        ..(*c).into()
+                    })
+                    .collect();
+                bar.upload(&pos);
+                bar.set_range(0..drawn);
+            }
+            BarRenderer::Gpu(bar) => {
+                bar.set_time(t);
+                bar.set_drawn(drawn);
+            }
+        }
+
+        if let Some(markers) = &mut self.markers {
+            markers.update(&positions[..drawn.min(positions.len())]);
+        }
+
+        if let Some(spectrum) = &mut self.spectrum {
+            spectrum.set_shown(self.cutoff);
+        }
+
+        for ghost in &mut self.ghosts {
+            ghost.outline_buffer.fill_to(t);
+            ghost.outline_gpu.set_range(ghost.outline_buffer.visible());
+        }
+
+        self.outline_buffer.fill_to(t);
+        self.outline_gpu.set_gradient_offset(self.gradient_speed * t);
+        self.outline_gpu.set_range(self.outline_buffer.visible());
+
+        if let Some(traces) = &mut self.traces {
+            traces.set_time(t, self.outline_buffer.cursor == self.outline_buffer.size);
+        }
     }
 
-    pub fn get_bar_pos(&self, t: f32) -> Vec<BarVertex> {
-        let result:Vec<BarVertex> = get_bar_pos_iter(&self.bars, t).map(|c| c.into()).collect();
-        result
+    pub fn draw<S: Surface>(&mut self, surface: &mut S) {
+        let scale = aspect_scale(surface);
+
+        match &mut self.bar_gpu {
+            BarRenderer::Cpu(bar) => {
+                bar.set_scale(scale);
+                bar.draw(surface).unwrap();
+            }
+            BarRenderer::Gpu(bar) => {
+                bar.set_scale(scale);
+                bar.draw(surface).unwrap();
+            }
+        }
+
+        for ghost in &mut self.ghosts {
+            ghost.outline_gpu.set_scale(scale);
+            ghost.outline_gpu.draw(surface).unwrap();
+        }
+
+        self.outline_gpu.set_scale(scale);
+        self.outline_gpu.draw(surface).unwrap();
+
+        if let Some(markers) = &mut self.markers {
+            markers.draw(surface);
+        }
     }
 
-    pub fn calc_next_bar_pos(&mut self) {
-        let val = get_bar_pos_iter(&self.bars, self.outline_buffer.percent_full()).last().unwrap().into();
-        self.outline_buffer.push(val);
+    // Traces are drawn onto the whole scene, outside the canvas.
+    pub fn draw_traces<S: Surface>(&mut self, surface: &mut S, regions: &Regions) {
+        if let Some(traces) = &mut self.traces {
+            traces.draw(surface, regions, self.outline_buffer.as_slice());
+        }
+    }
+
+    // Drawn separately from the line so that no other line covers the panel.
+    pub fn draw_spectrum<S: Surface>(&mut self, surface: &mut S) {
+        if let Some(spectrum) = &mut self.spectrum {
+            spectrum.draw(surface);
+        }
     }
 }
 
-fn get_bar_pos_iter(
-    bars: &[(i32, Complex32)],
+struct Phasors {
+    step: f32,
     t: f32,
-) -> impl Iterator<Item = Complex32> + use<'_> {
+    rotations: Vec<Complex32>,
+    current: Vec<Complex32>,
+    since_renorm: u32,
+}
+
+impl Phasors {
+    fn new(bars: &[(i32, Complex32)], step: f32) -> Self {
+        Self {
+            step,
+            t: 0.0,
+            rotations: bars
+                .iter()
+                .map(|(rot, _)| {
+                    let c = Complex64::cis(2.0 * PI64 * f64::from(*rot) * f64::from(step));
+                    Complex32::new(c.re as f32, c.im as f32)
+                })
+                .collect(),
+            current: bars.iter().map(|(_, c)| *c).collect(),
+            since_renorm: 0,
+        }
+    }
+
+    fn seek(&mut self, bars: &[(i32, Complex32)], t: f32) {
+        if (t - self.t - self.step).abs() > self.step * 1e-3 {
+            self.set(bars, t);
+            return;
+        }
+
+        self.t = t;
+        for (cur, rot) in self.current.iter_mut().zip(&self.rotations) {
+            *cur *= rot;
+        }
+
+        self.since_renorm += 1;
+        if self.since_renorm >= RENORM_INTERVAL {
+            for (cur, (_, coef)) in self.current.iter_mut().zip(bars) {
+                let norm = cur.norm();
+                if norm > 0.0 {
+                    *cur = cur.scale(coef.norm() / norm);
+                }
+            }
+            self.since_renorm = 0;
+        }
+    }
+
+    fn set(&mut self, bars: &[(i32, Complex32)], t: f32) {
+        self.t = t;
+        self.since_renorm = 0;
+        for (cur, (rot, coef)) in self.current.iter_mut().zip(bars) {
+            *cur = coef * Complex32::cis(2.0 * PI * (*rot as f32) * t);
+        }
+    }
+}
+
+fn get_bar_pos_iter(phasors: &[Complex32]) -> impl Iterator<Item = Complex32> + use<'_> {
+    phasors.iter().scan(Complex32::ZERO, |state, new| {
+        *state += new;
+        Some(*state)
+    })
+}
+
+fn max_harmonic(bars: &[(i32, Complex32)]) -> u32 {
+    bars.iter().map(|(rot, _)| rot.unsigned_abs()).max().unwrap_or(0)
+}
+
+// Length of the bar chain up to each joint. Bars only rotate, so this never changes.
+fn bar_distances(bars: &[(i32, Complex32)]) -> Vec<f32> {
     bars.iter()
-        .map(move |(rot, cur)| {
-            Complex32::from_polar(
-                cur.norm(),
-                cur.arg() + (2.0 * PI * f32::from(i16::try_from(*rot).unwrap()) * t),
-            )
+        .enumerate()
+        .scan(0.0, |distance, (i, (_, coef))| {
+            if i > 0 {
+                *distance += coef.norm();
+            }
+            Some(*distance)
         })
-        .scan(Complex32::ZERO, |state, new| {
-            *state += new;
-            Some(*state)
+        .collect()
+}
+
+// Maps each bar onto [0, 1] for colouring.
+fn bar_params(bars: &[(i32, Complex32)], shading: BarShading) -> Vec<f32> {
+    let max_rot = bars.iter().map(|(rot, _)| rot.abs()).max().unwrap_or(0).max(1) as f32;
+    let max_norm = bars.iter().map(|(_, c)| c.norm()).fold(0.0, f32::max).max(f32::EPSILON);
+
+    bars.iter()
+        .map(|(rot, coef)| match shading {
+            BarShading::Frequency => rot.abs() as f32 / max_rot,
+            // Kept off the ends, which meet on a cyclic colourmap.
+            BarShading::Rotation => 0.5 + 0.25 * rot.signum() as f32,
+            BarShading::Amplitude => coef.norm() / max_norm,
         })
+        .collect()
+}
+
+fn outline_vertices(points: Vec<Complex32>, hidden: &[Range<f32>]) -> Vec<LineVertex> {
+    let samples = points.len() as f32;
+    let mut distance = 0.0;
+    let mut previous = points.first().copied().unwrap_or_default();
+
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            distance += (p - previous).norm();
+            previous = p;
+            let param = i as f32 / samples;
+            LineVertex {
+                alpha: if hidden.iter().any(|range| range.contains(&param)) { 0.0 } else { 1.0 },
+                param,
+                distance,
+                ..p.into()
+            }
+        })
+        .collect()
+}
+
+fn inverse_transform(bars: &[(i32, Complex32)], samples: usize) -> Vec<Complex32> {
+    // Evaluate on a grid at least as fine as the highest frequency so that no
+    // coefficients alias onto each other, then keep every `stride`th point.
+    let stride = bars.len().div_ceil(samples).max(1);
+    let grid = samples * stride;
+
+    let mut spectrum = vec![Complex32::ZERO; grid];
+    for (rot, coef) in bars {
+        spectrum[rot.rem_euclid(grid as i32) as usize] += coef;
+    }
+
+    FftPlanner::new().plan_fft_inverse(grid).process(&mut spectrum);
+
+    spectrum.into_iter().step_by(stride).collect()
 }
 
 fn fourier_transform(points: &[Complex32]) -> Vec<(i32, Complex32)> {
     let mut result: Vec<(i32, _)> = (0..=points.len() / 2)
-        .map(|n| {
+        .flat_map(|n| {
             let n = n as i32;
             if n == 0 {
                 vec![n]
@@ -93,7 +470,6 @@ fn fourier_transform(points: &[Complex32]) -> Vec<(i32, Complex32)> {
                 vec![n, -n]
             }
         })
-        .flatten()
         .map(|n| (n, get_fourier_coef(points, n)))
         .collect();
 
@@ -104,11 +480,13 @@ fn fourier_transform(points: &[Complex32]) -> Vec<(i32, Complex32)> {
     result
 }
 
-fn normalise(points: &mut [Complex32]) {
+// Scales every route by the same factor, so that routes traced from one
+// source keep their sizes and places relative to each other.
+pub fn normalise(routes: &mut [Route]) {
     let mut ranges = (0_f32..0_f32, 0_f32..0_f32);
     //let mut center = Complex32::ZERO;
 
-    for p in points.iter() {
+    for p in routes.iter().flat_map(|route| &route.points) {
         if p.re > ranges.0.end {
             ranges.0.end = p.re
         };
@@ -132,7 +510,7 @@ fn normalise(points: &mut [Complex32]) {
             .abs()
             .max((ranges.1.end - ranges.1.start).abs());
 
-    for p in points {
+    for p in routes.iter_mut().flat_map(|route| &mut route.points) {
         //*p -= center;
         *p = p.scale(scale);
     }
@@ -152,3 +530,187 @@ fn get_fourier_coef(points: &[Complex32], index: i32) -> Complex32 {
         .unwrap()
         .scale(1.0 / p_len)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glium::{texture::RawImage2d, Texture2d};
+
+    use crate::{gpu_bar, headless, parsing::IniData, polyline::AlphaMode, signal::Waveform};
+
+    fn square(n: usize) -> Vec<Complex32> {
+        let corners = [
+            Complex32::new(-1.0, -1.0),
+            Complex32::new(1.0, -1.0),
+            Complex32::new(1.0, 1.0),
+            Complex32::new(-1.0, 1.0),
+        ];
+        (0..n)
+            .map(|i| {
+                let s = 4.0 * i as f32 / n as f32;
+                let side = s as usize;
+                corners[side] + (corners[(side + 1) % 4] - corners[side]).scale(s.fract())
+            })
+            .collect()
+    }
+
+    fn circle(n: usize, radius: f32) -> Vec<Complex32> {
+        (0..n)
+            .map(|i| Complex32::from_polar(radius, 2.0 * PI * i as f32 / n as f32))
+            .collect()
+    }
+
+    #[test]
+    fn circle_has_a_single_coefficient() {
+        let bars = fourier_transform(&circle(32, 0.5));
+
+        for (rot, coef) in bars {
+            let expected = if rot == 1 { 0.5 } else { 0.0 };
+            assert!((coef.norm() - expected).abs() < 1e-5, "coefficient {rot} = {coef}");
+        }
+    }
+
+    #[test]
+    fn real_signals_give_conjugate_paired_epicycles() {
+        let bars = fourier_transform(&Waveform::Sawtooth.points(8));
+
+        // The signal lies on the imaginary axis, so each pair satisfies
+        // c_-n = -conj(c_n) and their sum stays on that axis.
+        for pair in bars[1..].chunks_exact(2) {
+            let ((n, positive), (m, negative)) = (pair[0], pair[1]);
+            assert_eq!(n, -m);
+            assert!((negative + positive.conj()).norm() < 1e-5, "pair {n}: {positive} and {negative}");
+            assert!((positive.norm() - 1.0 / (PI * n as f32)).abs() < 1e-4, "coefficient {n} = {positive}");
+        }
+    }
+
+    #[test]
+    fn fourier_transform_reconstructs_shapes() {
+        for points in [circle(32, 0.5), square(64), square(37)] {
+            let bars = fourier_transform(&points);
+            assert_eq!(bars.len(), points.len());
+
+            let outline = inverse_transform(&bars, points.len());
+            for (p, o) in points.iter().zip(&outline) {
+                assert!((p - o).norm() < 1e-4, "expected {p}, got {o}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_transform_matches_bar_chain() {
+        let bars = fourier_transform(&square(64));
+
+        // Fewer samples than bars exercises the oversampled grid.
+        for samples in [10, 64, 500] {
+            let outline = inverse_transform(&bars, samples);
+            assert_eq!(outline.len(), samples);
+
+            let mut phasors = Phasors::new(&bars, 1.0 / samples as f32);
+            for (i, o) in outline.iter().enumerate() {
+                phasors.set(&bars, i as f32 / samples as f32);
+                let tip = get_bar_pos_iter(&phasors.current).last().unwrap();
+                assert!((tip - o).norm() < 1e-4, "sample {i}: expected {tip}, got {o}");
+            }
+        }
+    }
+
+    #[test]
+    fn normalise_fits_unit_range() {
+        let mut routes = [
+            Route::from(vec![Complex32::new(-4.0, 1.0), Complex32::new(6.0, 2.0)]),
+            Route::from(vec![Complex32::new(0.0, -3.0)]),
+        ];
+        normalise(&mut routes);
+
+        assert_eq!(routes[0].points, [Complex32::new(-0.4, 0.1), Complex32::new(0.6, 0.2)]);
+        assert_eq!(routes[1].points, [Complex32::new(0.0, -0.3)]);
+    }
+
+    #[test]
+    fn rotation_shading_tells_directions_apart_on_cyclic_maps() {
+        let bars = [(0, Complex32::ONE), (1, Complex32::ONE), (-1, Complex32::ONE)];
+        assert_eq!(bar_params(&bars, BarShading::Rotation), [0.5, 0.75, 0.25]);
+    }
+
+    #[test]
+    fn gpu_bars_match_cpu() {
+        let facade = headless::context(1, 1);
+
+        let mut routes = [Route::from(square(256))];
+        normalise(&mut routes);
+        let bars = fourier_transform(&routes[0].points);
+
+        let program = Rc::new(gpu_bar::get_program(&facade).unwrap());
+        let params = vec![0.0; bars.len()];
+        let style = LineStyle {
+            colour: "white".parse().unwrap(),
+            width: 1.0,
+            alpha_mode: AlphaMode::Blend,
+            dash: None,
+        };
+        let gpu = GpuBar::new(&facade, &bars, &params, program, &style, None);
+
+        let step = 1.0 / 119.0;
+        let mut phasors = Phasors::new(&bars, step);
+        let mut t = 0.0;
+
+        for _ in 0..120 {
+            phasors.seek(&bars, t);
+            let cpu: Vec<_> = get_bar_pos_iter(&phasors.current).collect();
+            let gpu = gpu.positions(&facade, t);
+
+            assert_eq!(cpu.len(), gpu.len());
+            for (i, (c, g)) in cpu.iter().zip(&gpu).enumerate() {
+                assert!((c - g).norm() < 1e-3, "t = {t}, bar {i}: cpu {c}, gpu {g}");
+            }
+
+            t += step;
+        }
+    }
+
+    #[test]
+    fn one_update_draws_into_several_surfaces() {
+        let facade = headless::context(1, 1);
+        let mut args = IniData::parse_ini("tests/golden/star.ini", &facade).unwrap();
+        let textures = [256, 256, 64].map(|size| Texture2d::empty(&facade, size, size).unwrap());
+
+        for line in &mut args.lines {
+            line.update(0.5);
+        }
+        for texture in &textures {
+            let mut surface = texture.as_surface();
+            surface.clear_color(0.0, 0.0, 0.0, 1.0);
+            for line in &mut args.lines {
+                line.draw(&mut surface);
+            }
+        }
+
+        let pixels = |texture: &Texture2d| {
+            let image: RawImage2d<u8> = texture.read();
+            image.data.into_owned()
+        };
+        let full = pixels(&textures[0]);
+        assert!(full.chunks_exact(4).any(|p| p[..3] != [0, 0, 0]));
+        assert_eq!(full, pixels(&textures[1]));
+        assert!(pixels(&textures[2]).chunks_exact(4).any(|p| p[..3] != [0, 0, 0]));
+    }
+
+    #[test]
+    fn spectrum_highlight_follows_the_animated_cutoff() {
+        let facade = headless::context(1, 1);
+        let mut args = IniData::parse_ini("tests/golden/spectrum.ini", &facade).unwrap();
+        let line = &mut args.lines[0];
+        let mut update = |t| {
+            line.update(t);
+            (line.drawn_bars(), line.spectrum.as_ref().unwrap().shown())
+        };
+
+        assert_eq!(update(0.0), (3, Some(1)));
+        assert_eq!(update(0.5), (15, Some(7)));
+        assert_eq!(update(1.0), (25, Some(12)));
+        // The next period starts again from the lowest harmonics.
+        assert_eq!(update(0.0), (3, Some(1)));
+    }
+}
@@ -0,0 +1,137 @@
+use ab_glyph::{Font, OutlineCurve, Point};
+
+use crate::Complex32;
+
+// Points each Bezier curve is flattened into. Routes are resampled evenly
+// afterwards, so this only needs to follow the curve closely.
+const CURVE_STEPS: usize = 8;
+
+// The closed contours of each glyph in `text`, laid out along one line with
+// kerning. Glyphs without an outline, such as spaces, are left out. Points
+// are in font units with y up, centred on the text's bounding box.
+pub fn text_contours(font: &impl Font, text: &str) -> Vec<Vec<Vec<Complex32>>> {
+    let mut glyphs = vec![];
+    let mut x = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            x += font.kern_unscaled(previous, id);
+        }
+        if let Some(outline) = font.outline(id) {
+            let contours = flatten(&outline.curves, x);
+            if !contours.is_empty() {
+                glyphs.push(contours);
+            }
+        }
+        x += font.h_advance_unscaled(id);
+        previous = Some(id);
+    }
+
+    let points = || glyphs.iter().flatten().flatten();
+    let min = points().fold(Complex32::new(f32::MAX, f32::MAX), |m, p| Complex32::new(m.re.min(p.re), m.im.min(p.im)));
+    let max = points().fold(Complex32::new(f32::MIN, f32::MIN), |m, p| Complex32::new(m.re.max(p.re), m.im.max(p.im)));
+    let centre = (min + max) / 2.0;
+
+    for p in glyphs.iter_mut().flatten().flatten() {
+        *p -= centre;
+    }
+    glyphs
+}
+
+// A new contour starts wherever a curve doesn't carry on from the last one.
+fn flatten(curves: &[OutlineCurve], offset: f32) -> Vec<Vec<Complex32>> {
+    let to_complex = |p: Point| Complex32::new(p.x + offset, p.y);
+    let mut contours: Vec<Vec<Complex32>> = vec![];
+
+    for curve in curves {
+        let points: Vec<Complex32> = match *curve {
+            OutlineCurve::Line(_, b) => vec![to_complex(b)],
+            OutlineCurve::Quad(a, b, c) => {
+                let [a, b, c] = [a, b, c].map(to_complex);
+                steps().map(|t| a * (1.0 - t).powi(2) + b * 2.0 * t * (1.0 - t) + c * t * t).collect()
+            }
+            OutlineCurve::Cubic(a, b, c, d) => {
+                let [a, b, c, d] = [a, b, c, d].map(to_complex);
+                steps()
+                    .map(|t| {
+                        let s = 1.0 - t;
+                        a * s * s * s + b * 3.0 * s * s * t + c * 3.0 * s * t * t + d * t * t * t
+                    })
+                    .collect()
+            }
+        };
+
+        let start = to_complex(curve_start(curve));
+        match contours.last_mut() {
+            Some(contour) if contour.last() == Some(&start) => contour.extend(points),
+            _ => contours.push([vec![start], points].concat()),
+        }
+    }
+
+    // Contours end back at their start, which a route closes by itself.
+    for contour in &mut contours {
+        if contour.len() > 1 && contour.first() == contour.last() {
+            contour.pop();
+        }
+    }
+    contours.retain(|contour| contour.len() >= 3);
+    contours
+}
+
+fn curve_start(curve: &OutlineCurve) -> Point {
+    match *curve {
+        OutlineCurve::Line(a, _) | OutlineCurve::Quad(a, _, _) | OutlineCurve::Cubic(a, _, _, _) => a,
+    }
+}
+
+fn steps() -> impl Iterator<Item = f32> {
+    (1..=CURVE_STEPS).map(|i| i as f32 / CURVE_STEPS as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use ab_glyph::{point, FontRef};
+
+    use super::*;
+    use crate::text::FONT;
+
+    #[test]
+    fn curves_flatten_into_closed_contours() {
+        let curves = [
+            OutlineCurve::Line(point(0.0, 0.0), point(2.0, 0.0)),
+            OutlineCurve::Quad(point(2.0, 0.0), point(2.0, 2.0), point(0.0, 2.0)),
+            OutlineCurve::Line(point(0.0, 2.0), point(0.0, 0.0)),
+            OutlineCurve::Line(point(5.0, 5.0), point(6.0, 5.0)),
+            OutlineCurve::Line(point(6.0, 5.0), point(5.0, 6.0)),
+            OutlineCurve::Line(point(5.0, 6.0), point(5.0, 5.0)),
+        ];
+        let contours = flatten(&curves, 10.0);
+
+        assert_eq!(contours.len(), 2);
+        assert_eq!(contours[0].len(), 2 + CURVE_STEPS);
+        assert_eq!(contours[0][0], Complex32::new(10.0, 0.0));
+        assert_eq!(contours[0][1 + CURVE_STEPS], Complex32::new(10.0, 2.0));
+        // Halfway along the quadratic.
+        assert_eq!(contours[0][1 + CURVE_STEPS / 2], Complex32::new(11.5, 1.5));
+        assert_eq!(contours[1], [Complex32::new(15.0, 5.0), Complex32::new(16.0, 5.0), Complex32::new(15.0, 6.0)]);
+    }
+
+    #[test]
+    fn text_is_laid_out_left_to_right_and_centred() {
+        let font = FontRef::try_from_slice(FONT).unwrap();
+        let glyphs = text_contours(&font, "o i");
+
+        // "o" has an inside and an outside, "i" a stem and a dot, and the
+        // space has no outline at all.
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs[0].len(), 2);
+        assert_eq!(glyphs[1].len(), 2);
+
+        let right = |contours: &[Vec<Complex32>]| contours.iter().flatten().map(|p| p.re).fold(f32::MIN, f32::max);
+        let left = |contours: &[Vec<Complex32>]| contours.iter().flatten().map(|p| p.re).fold(f32::MAX, f32::min);
+        assert!(right(&glyphs[0]) < left(&glyphs[1]));
+        assert!((left(&glyphs[0]) + right(&glyphs[1])).abs() < 1e-3);
+    }
+}
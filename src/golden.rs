@@ -0,0 +1,254 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use glium::{texture::RawImage2d, Frame};
+
+use crate::{
+    headless,
+    parsing::IniData,
+    render_target::{flip_rows, write_png},
+};
+
+const SIZE: u32 = 256;
+const REFERENCE_DIR: &str = "tests/golden";
+const OUTPUT_DIR: &str = "target/golden";
+
+// Squared YIQ distance, as used by pixelmatch, above which a pixel counts as
+// visibly different. The maximum possible distance is about 35215.
+const PIXEL_THRESHOLD: f32 = 0.1 * 0.1 * 35215.0;
+// Fraction of visibly different pixels tolerated before a frame is rejected.
+const MAX_CHANGED: f32 = 0.002;
+
+struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Image {
+    fn load(path: &Path) -> Image {
+        let decoder = png::Decoder::new(File::open(path).unwrap_or_else(|e| {
+            panic!("Unable to open {}: {e}. Run with UPDATE_GOLDEN=1 to create it.", path.display())
+        }));
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!(info.color_type, png::ColorType::Rgba, "{} is not RGBA", path.display());
+        data.truncate(info.buffer_size());
+
+        Image {
+            width: info.width,
+            height: info.height,
+            data,
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        write_png(path, self.width, self.height, &self.data).unwrap();
+    }
+}
+
+fn render(config: &str, times: &[f32]) -> Vec<Image> {
+    let context = headless::context(SIZE, SIZE);
+    let mut args = IniData::parse_ini(config, &context).unwrap();
+
+    times
+        .iter()
+        .map(|&t| {
+            let mut target = Frame::new(context.clone(), (SIZE, SIZE));
+            args.draw(&mut target, t).unwrap();
+            target.finish().unwrap();
+
+            let image: RawImage2d<u8> = context.read_front_buffer().unwrap();
+            Image {
+                width: image.width,
+                height: image.height,
+                data: flip_rows(image),
+            }
+        })
+        .collect()
+}
+
+fn yiq_distance(a: &[u8], b: &[u8]) -> f32 {
+    let yiq = |p: &[u8]| {
+        let (r, g, b) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        (
+            0.2988953 * r + 0.5866225 * g + 0.1144822 * b,
+            0.595978 * r - 0.2741761 * g - 0.3218019 * b,
+            0.2114702 * r - 0.5226171 * g + 0.3111469 * b,
+        )
+    };
+    let (y1, i1, q1) = yiq(a);
+    let (y2, i2, q2) = yiq(b);
+    0.5053 * (y1 - y2).powi(2) + 0.299 * (i1 - i2).powi(2) + 0.1957 * (q1 - q2).powi(2)
+}
+
+// Returns the number of visibly different pixels and an image highlighting them
+// in red over a faded copy of the reference.
+fn compare(expected: &Image, actual: &Image) -> (usize, Image) {
+    let mut changed = 0;
+    let data = expected
+        .data
+        .chunks_exact(4)
+        .zip(actual.data.chunks_exact(4))
+        .flat_map(|(e, a)| {
+            if yiq_distance(e, a) > PIXEL_THRESHOLD {
+                changed += 1;
+                [255, 0, 0, 255]
+            } else {
+                let luma = (0.299 * e[0] as f32 + 0.587 * e[1] as f32 + 0.114 * e[2] as f32) as u8;
+                let faded = 255 - (255 - luma) / 4;
+                [faded, faded, faded, 255]
+            }
+        })
+        .collect();
+
+    let diff = Image {
+        width: expected.width,
+        height: expected.height,
+        data,
+    };
+    (changed, diff)
+}
+
+fn check(name: &str, times: &[f32]) {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let frames = render(&format!("{REFERENCE_DIR}/{name}.ini"), times);
+    let mut failures = vec![];
+
+    for (t, actual) in times.iter().zip(frames) {
+        let file = format!("{name}_{:03}.png", (t * 100.0).round() as u32);
+        let reference = PathBuf::from(REFERENCE_DIR).join(&file);
+
+        if update {
+            actual.save(&reference);
+            continue;
+        }
+
+        let expected = Image::load(&reference);
+        assert_eq!(
+            (expected.width, expected.height),
+            (actual.width, actual.height),
+            "{file} has the wrong dimensions"
+        );
+
+        let (changed, diff) = compare(&expected, &actual);
+        let fraction = changed as f32 / (actual.width * actual.height) as f32;
+        if fraction > MAX_CHANGED {
+            let output = PathBuf::from(OUTPUT_DIR);
+            actual.save(&output.join(format!("{name}_{:03}_actual.png", (t * 100.0).round() as u32)));
+            diff.save(&output.join(format!("{name}_{:03}_diff.png", (t * 100.0).round() as u32)));
+            failures.push(format!("{file}: {changed} pixels differ ({:.2}%)", fraction * 100.0));
+        }
+    }
+
+    assert!(failures.is_empty(), "Golden image mismatch, see {OUTPUT_DIR}:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn square() {
+    check("square", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn circle() {
+    check("circle", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn star() {
+    check("star", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn gradient() {
+    check("gradient", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn colourmap() {
+    check("colourmap", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn smooth() {
+    check("smooth", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn supersampled() {
+    check("supersampled", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn widescreen() {
+    check("widescreen", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn styled() {
+    check("styled", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn dashed() {
+    check("dashed", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn markers() {
+    check("markers", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn hud() {
+    check("hud", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn guides() {
+    check("guides", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn spectrum() {
+    check("spectrum", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn traces() {
+    check("traces", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn signal() {
+    check("signal", &[0.1, 0.35, 0.8]);
+}
+
+#[test]
+fn shapes() {
+    check("shapes", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn expressions() {
+    check("expressions", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn image() {
+    check("image", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn joined() {
+    check("joined", &[0.25, 0.5, 1.0]);
+}
+
+#[test]
+fn text() {
+    check("text", &[0.25, 0.5, 1.0]);
+}
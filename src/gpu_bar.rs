@@ -0,0 +1,233 @@
+use std::{borrow::Cow, rc::Rc};
+
+use glium::{
+    backend::Facade,
+    draw_parameters::Smooth,
+    index::{NoIndices, PrimitiveType},
+    program::{ProgramCreationError, ProgramCreationInput, TransformFeedbackMode},
+    texture::{MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat},
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, UniformValue, Uniforms},
+    vertex::EmptyVertexAttributes,
+    DrawError, DrawParameters, Program, Surface,
+};
+
+use crate::{
+    colour::Gradient,
+    graphics::{line_fragment_shader, LineUniform},
+    polyline::LineStyle,
+    Complex32,
+};
+
+// Coefficients are packed row by row into a texture of this width, which
+// stays under the minimum GL_MAX_TEXTURE_SIZE guaranteed by OpenGL 3.
+const TEXTURE_WIDTH: usize = 1024;
+
+pub struct GpuBar<'a> {
+    len: usize,
+    drawn: usize,
+    coefficients: Texture2d,
+    program: Rc<Program>,
+    line_uniform: LineUniform,
+    params: DrawParameters<'a>,
+    width: f32,
+    t: f32,
+}
+
+struct GpuBarUniform<'a> {
+    line: &'a LineUniform,
+    coefficients: &'a Texture2d,
+    t: f32,
+}
+
+impl Uniforms for GpuBarUniform<'_> {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut output: F) {
+        let sampler = SamplerBehavior {
+            minify_filter: MinifySamplerFilter::Nearest,
+            magnify_filter: MagnifySamplerFilter::Nearest,
+            ..Default::default()
+        };
+
+        output("coefficients", UniformValue::Texture2d(self.coefficients, Some(sampler)));
+        output("t", UniformValue::Float(self.t));
+        self.line.visit_values(output);
+    }
+}
+
+impl<'a> GpuBar<'a> {
+    pub fn new(
+        facade: &impl Facade,
+        bars: &[(i32, Complex32)],
+        params: &[f32],
+        program: Rc<Program>,
+        style: &LineStyle,
+        colourmap: Option<&Gradient>,
+    ) -> Self {
+        let width = bars.len().clamp(1, TEXTURE_WIDTH);
+        let height = bars.len().div_ceil(width).max(1);
+
+        let mut texels = vec![0_f32; width * height * 4];
+        for ((texel, (rot, coef)), param) in texels.chunks_exact_mut(4).zip(bars).zip(params) {
+            texel.copy_from_slice(&[coef.re, coef.im, *rot as f32, *param]);
+        }
+
+        let image = RawImage2d {
+            data: Cow::Owned(texels),
+            width: width as u32,
+            height: height as u32,
+            format: glium::texture::ClientFormat::F32F32F32F32,
+        };
+
+        let mut line_uniform = LineUniform::new(facade, style.colour, colourmap);
+        line_uniform.dash = style.dash.unwrap_or_default();
+
+        Self {
+            len: bars.len(),
+            drawn: bars.len(),
+            coefficients: Texture2d::with_format(
+                facade,
+                image,
+                UncompressedFloatFormat::F32F32F32F32,
+                MipmapsOption::NoMipmap,
+            )
+            .unwrap(),
+            program,
+            line_uniform,
+            params: DrawParameters {
+                blend: style.alpha_mode.blend(),
+                line_width: Some(style.width),
+                ..Default::default()
+            },
+            width: style.width,
+            t: 0.0,
+        }
+    }
+
+    pub fn set_time(&mut self, t: f32) {
+        self.t = t;
+    }
+
+    pub fn set_scale(&mut self, scale: [f32; 2]) {
+        self.line_uniform.scale = scale;
+    }
+
+    // Draws only the first `drawn` bars of the chain.
+    pub fn set_drawn(&mut self, drawn: usize) {
+        self.drawn = drawn.min(self.len);
+    }
+
+    pub fn set_line_hints(&mut self, smooth: Option<Smooth>, width_scale: f32) {
+        self.params.smooth = smooth;
+        self.params.line_width = Some(self.width * width_scale);
+    }
+
+    pub fn draw<S: Surface>(&self, surface: &mut S) -> Result<(), DrawError> {
+        self.draw_primitive(surface, self.t, self.drawn, PrimitiveType::LineStrip, &self.params)
+    }
+
+    fn draw_primitive<S: Surface>(
+        &self,
+        surface: &mut S,
+        t: f32,
+        len: usize,
+        primitive: PrimitiveType,
+        params: &DrawParameters,
+    ) -> Result<(), DrawError> {
+        let uniforms = GpuBarUniform {
+            line: &self.line_uniform,
+            coefficients: &self.coefficients,
+            t,
+        };
+
+        surface.draw(
+            EmptyVertexAttributes { len },
+            NoIndices(primitive),
+            &self.program,
+            &uniforms,
+            params,
+        )
+    }
+
+    #[cfg(test)]
+    pub fn positions(&self, facade: &impl Facade, t: f32) -> Vec<Complex32> {
+        use glium::{framebuffer::EmptyFrameBuffer, vertex::TransformFeedbackSession, VertexBuffer};
+
+        #[derive(Copy, Clone)]
+        struct Feedback {
+            v_position: [f32; 2],
+        }
+        glium::implement_vertex!(Feedback, v_position);
+
+        let mut output: VertexBuffer<Feedback> = VertexBuffer::empty(facade, self.len).unwrap();
+        {
+            let session = TransformFeedbackSession::new(facade, &self.program, &mut output).unwrap();
+            let params = DrawParameters {
+                transform_feedback: Some(&session),
+                draw_primitives: false,
+                ..Default::default()
+            };
+            let mut surface = EmptyFrameBuffer::new(facade, 1, 1, None, None, false).unwrap();
+            self.draw_primitive(&mut surface, t, self.len, PrimitiveType::Points, &params).unwrap();
+        }
+
+        output
+            .read()
+            .unwrap()
+            .into_iter()
+            .map(|f| Complex32::new(f.v_position[0], f.v_position[1]))
+            .collect()
+    }
+}
+
+pub fn get_program(facade: &impl Facade) -> Result<Program, ProgramCreationError> {
+    let vertex_shader_src = r#"
+            #version 140
+
+            uniform sampler2D coefficients;
+            uniform float t;
+            uniform vec2 scale;
+
+            out vec2 v_position;
+            out float v_alpha;
+            flat out float v_param;
+            out float v_distance;
+
+            void main() {
+                int width = textureSize(coefficients, 0).x;
+                vec2 pos = vec2(0.0);
+                float distance = 0.0;
+
+                for (int i = 0; i <= gl_VertexID; i++) {
+                    vec4 coef = texelFetch(coefficients, ivec2(i % width, i / width), 0);
+                    float theta = 6.2831853 * fract(coef.z * t);
+                    vec2 rot = vec2(cos(theta), sin(theta));
+                    pos += vec2(coef.x * rot.x - coef.y * rot.y, coef.x * rot.y + coef.y * rot.x);
+                    distance += i > 0 ? length(coef.xy) : 0.0;
+                }
+
+                v_position = pos;
+                v_alpha = 1.0;
+                v_distance = distance;
+                v_param = texelFetch(coefficients, ivec2(gl_VertexID % width, gl_VertexID / width), 0).w;
+                gl_Position = vec4(pos * scale, 0.0, 1.0);
+            }
+        "#;
+
+    let fragment_shader_src = line_fragment_shader(true);
+
+    Program::new(
+        facade,
+        ProgramCreationInput::SourceCode {
+            vertex_shader: vertex_shader_src,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+            fragment_shader: &fragment_shader_src,
+            transform_feedback_varyings: Some((
+                vec!["v_position".to_string()],
+                TransformFeedbackMode::Interleaved,
+            )),
+            outputs_srgb: false,
+            uses_point_size: false,
+        },
+    )
+}
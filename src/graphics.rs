@@ -1,24 +1,32 @@
-use std::sync::Arc;
+use std::{ops::Range, rc::Rc};
 
 use glium::{
-    index::NoIndices,
-    uniforms::Uniforms,
-    DrawError, DrawParameters, Frame, Program, Surface, VertexBuffer,
+    backend::Facade,
+    framebuffer::{MultiOutputFrameBuffer, SimpleFrameBuffer},
+    index::{IndicesSource, NoIndices, PrimitiveType},
+    program::{ProgramCreationError, ProgramCreationInput},
+    texture::{MipmapsOption, Texture1d, UncompressedFloatFormat},
+    uniforms::{AsUniformValue, MagnifySamplerFilter, SamplerBehavior, SamplerWrapFunction, UniformValue, Uniforms},
+    vertex::{EmptyVertexAttributes, MultiVerticesSource},
+    Blend, BlitMask, BlitTarget, CapabilitiesSource, DrawError, DrawParameters, Program, Rect, Surface, VertexBuffer,
 };
 
+use crate::{colour::Gradient, Colour};
+
 pub struct DrawItem<'a, T: Copy, U: Uniforms> {
     name: &'static str,
     buffer: VertexBuffer<T>,
     indices: NoIndices,
-    program: Arc<Program>,
+    program: Rc<Program>,
     uniforms: U,
     params: DrawParameters<'a>,
+    range: Range<usize>,
 }
 
 pub trait Drawable<'a, T: Copy, U: Uniforms> {
     fn upload(&mut self, data: &[T]);
 
-    fn draw(&self, frame: &mut Frame) -> Result<(), DrawError>;
+    fn draw<S: Surface>(&self, surface: &mut S) -> Result<(), DrawError>;
 }
 
 impl<'a, T: Copy, U: Uniforms> DrawItem<'a, T, U> {
@@ -26,17 +34,43 @@ impl<'a, T: Copy, U: Uniforms> DrawItem<'a, T, U> {
         name: &'static str,
         buffer: VertexBuffer<T>,
         indices: NoIndices,
-        program: Arc<Program>,
+        program: Rc<Program>,
         uniforms: U,
     ) -> Self {
         Self {
             name,
+            range: 0..buffer.len(),
             buffer,
             indices,
             program,
             uniforms,
-            params: DrawParameters::default(),
+            params: DrawParameters {
+                blend: Blend::alpha_blending(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn uniforms_mut(&mut self) -> &mut U {
+        &mut self.uniforms
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>) {
+        if range.end > self.buffer.len() {panic!("Error when drawing {}: Range {:?} exceeds buffer size {}", self.name, range, self.buffer.len())}
+        self.range = range;
+    }
+
+    pub fn params_mut(&mut self) -> &mut DrawParameters<'a> {
+        &mut self.params
+    }
+
+    // Writes `data` to the start of the buffer and draws only that much of it.
+    pub fn upload_prefix(&mut self, data: &[T]) {
+        if data.len() > self.buffer.len() {panic!("Error when drawing {}: Expected at most {} vertices, got {}", self.name, self.buffer.len(), data.len())}
+        if let Some(slice) = self.buffer.slice_mut(0..data.len()) {
+            slice.write(data);
         }
+        self.range = 0..data.len();
     }
 }
 
@@ -47,13 +81,282 @@ impl<'a, T: Copy + std::fmt::Display, U: Uniforms> Drawable<'a, T, U> for DrawIt
         self.buffer.write(data)
     }
 
-    fn draw(&self, frame: &mut Frame) -> Result<(), DrawError> {
-        frame.draw(
-            &self.buffer,
-            &self.indices,
+    fn draw<S: Surface>(&self, surface: &mut S) -> Result<(), DrawError> {
+        let min_vertices = match self.indices.0 {
+            PrimitiveType::Points => 1,
+            PrimitiveType::TrianglesList | PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan => 3,
+            _ => 2,
+        };
+        if self.range.len() < min_vertices {
+            return Ok(());
+        }
+
+        surface.draw(
+            self.buffer.slice(self.range.clone()).unwrap(),
+            self.indices,
             &self.program,
             &self.uniforms,
             &self.params,
         )
     }
 }
+
+pub struct LineUniform {
+    colour: Colour,
+    gradient: Rc<Texture1d>,
+    cyclic: bool,
+    pub offset: f32,
+    pub scale: [f32; 2],
+    pub dash: [f32; 2],
+}
+
+impl LineUniform {
+    // Without a gradient the line is a flat `colour`, otherwise the gradient
+    // supplies the colours and `colour` only tints them.
+    pub fn new(facade: &impl Facade, colour: Colour, gradient: Option<&Gradient>) -> Self {
+        let texels = gradient.map_or(vec![(1.0, 1.0, 1.0, 1.0)], Gradient::texels);
+        let texture = Texture1d::with_format(
+            facade,
+            texels,
+            UncompressedFloatFormat::F32F32F32F32,
+            MipmapsOption::NoMipmap,
+        )
+        .unwrap();
+
+        Self {
+            colour,
+            gradient: Rc::new(texture),
+            cyclic: gradient.is_some_and(Gradient::is_cyclic),
+            offset: 0.0,
+            scale: [1.0, 1.0],
+            dash: [0.0, 0.0],
+        }
+    }
+}
+
+impl Uniforms for LineUniform {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut output: F) {
+        let wrap = if self.cyclic {
+            SamplerWrapFunction::Repeat
+        } else {
+            SamplerWrapFunction::Clamp
+        };
+        let sampler = SamplerBehavior {
+            wrap_function: (wrap, wrap, wrap),
+            ..Default::default()
+        };
+
+        output("vertex_colour", self.colour.as_uniform_value());
+        output("gradient", UniformValue::Texture1d(&self.gradient, Some(sampler)));
+        output("gradient_cyclic", UniformValue::Bool(self.cyclic));
+        output("gradient_offset", UniformValue::Float(self.offset));
+        output("scale", UniformValue::Vec2(self.scale));
+        output("dash", UniformValue::Vec2(self.dash));
+    }
+}
+
+// Scales clip space so the unit square stays square and fully visible on a
+// surface of any aspect ratio.
+pub fn aspect_scale<S: Surface>(surface: &S) -> [f32; 2] {
+    aspect_scale_of(surface.get_dimensions())
+}
+
+pub fn aspect_scale_of((width, height): (u32, u32)) -> [f32; 2] {
+    let aspect = width as f32 / height as f32;
+    if aspect > 1.0 {
+        [1.0 / aspect, 1.0]
+    } else {
+        [1.0, aspect]
+    }
+}
+
+// Fragment shader shared by every line program. `v_param` picks the colour
+// from the gradient; `flat` keeps it constant along each line segment.
+pub fn line_fragment_shader(flat: bool) -> String {
+    let interpolation = if flat { "flat " } else { "" };
+
+    format!(
+        r#"
+            #version 140
+
+            uniform vec4 vertex_colour;
+            uniform sampler1D gradient;
+            uniform bool gradient_cyclic;
+            uniform float gradient_offset;
+            uniform vec2 dash;
+
+            in float v_alpha;
+            {interpolation}in float v_param;
+            in float v_distance;
+            out vec4 color;
+
+            void main() {{
+                // `dash` holds the on and off lengths; no gap means a solid line.
+                if (dash.y > 0.0 && mod(v_distance, dash.x + dash.y) > dash.x) {{
+                    discard;
+                }}
+
+                float p = v_param + gradient_offset;
+                vec4 shade;
+
+                if (gradient_cyclic) {{
+                    shade = texture(gradient, p);
+                }} else {{
+                    // Bounce between the first and last stop so an animated
+                    // offset never jumps, and sample texel centres only.
+                    float n = float(textureSize(gradient, 0));
+                    float u = 1.0 - abs(1.0 - mod(p, 2.0));
+                    shade = texture(gradient, (0.5 + u * (n - 1.0)) / n);
+                }}
+
+                vec4 c = vertex_colour * shade;
+                color = vec4(c.r, c.g, c.b, c.a * v_alpha);
+            }}
+        "#
+    )
+}
+
+// Converts a linear colour to sRGB, for programs created with `outputs_srgb`
+// that sample sRGB textures and must encode their result themselves.
+pub const LINEAR_TO_SRGB: &str = r#"
+            vec4 linear_to_srgb(vec4 linear) {
+                vec3 srgb = mix(
+                    12.92 * linear.rgb,
+                    1.055 * pow(linear.rgb, vec3(1.0 / 2.4)) - 0.055,
+                    step(0.0031308, linear.rgb)
+                );
+                return vec4(srgb, linear.a);
+            }
+        "#;
+
+// Builds a program that draws one triangle covering the viewport, passing
+// `v_uv` in 0..1 to `fragment_shader`.
+pub fn fullscreen_program(facade: &impl Facade, fragment_shader: &str) -> Result<Program, ProgramCreationError> {
+    let vertex_shader_src = r#"
+            #version 140
+
+            out vec2 v_uv;
+
+            void main() {
+                vec2 pos = vec2(gl_VertexID == 1 ? 3.0 : -1.0, gl_VertexID == 2 ? 3.0 : -1.0);
+                v_uv = pos * 0.5 + 0.5;
+                gl_Position = vec4(pos, 0.0, 1.0);
+            }
+        "#;
+
+    Program::new(
+        facade,
+        ProgramCreationInput::SourceCode {
+            vertex_shader: vertex_shader_src,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+            fragment_shader,
+            transform_feedback_varyings: None,
+            outputs_srgb: true,
+            uses_point_size: false,
+        },
+    )
+}
+
+// Sizes past the driver's limit fail to allocate, so they are rejected up front.
+pub fn check_texture_size(facade: &impl Facade, (width, height): (u32, u32)) -> anyhow::Result<()> {
+    let max = facade.get_context().get_capabilities().max_texture_size as u32;
+    if width > max || height > max {
+        return Err(anyhow::anyhow!("Expected textures of at most {max}x{max}, got {width}x{height}"));
+    }
+    Ok(())
+}
+
+pub fn draw_fullscreen<S: Surface, U: Uniforms>(
+    surface: &mut S,
+    program: &Program,
+    uniforms: &U,
+    params: &DrawParameters,
+) -> Result<(), DrawError> {
+    surface.draw(
+        EmptyVertexAttributes { len: 3 },
+        NoIndices(PrimitiveType::TrianglesList),
+        program,
+        uniforms,
+        params,
+    )
+}
+
+// A rectangle of another surface that behaves like a surface of its own:
+// draws and clears land inside `rect` and nothing spills out of it.
+pub struct Viewport<'s, S: Surface> {
+    surface: &'s mut S,
+    rect: Rect,
+}
+
+impl<'s, S: Surface> Viewport<'s, S> {
+    pub fn new(surface: &'s mut S, rect: Rect) -> Self {
+        Self { surface, rect }
+    }
+
+    fn offset(&self, rect: &Rect) -> Rect {
+        Rect {
+            left: self.rect.left + rect.left,
+            bottom: self.rect.bottom + rect.bottom,
+            ..*rect
+        }
+    }
+
+    fn offset_target(&self, target: &BlitTarget) -> BlitTarget {
+        BlitTarget {
+            left: self.rect.left + target.left,
+            bottom: self.rect.bottom + target.bottom,
+            ..*target
+        }
+    }
+}
+
+impl<S: Surface> Surface for Viewport<'_, S> {
+    fn clear(&mut self, rect: Option<&Rect>, color: Option<(f32, f32, f32, f32)>, color_srgb: bool, depth: Option<f32>, stencil: Option<i32>) {
+        let rect = rect.map_or(self.rect, |rect| self.offset(rect));
+        self.surface.clear(Some(&rect), color, color_srgb, depth, stencil);
+    }
+
+    fn get_dimensions(&self) -> (u32, u32) {
+        (self.rect.width, self.rect.height)
+    }
+
+    fn get_depth_buffer_bits(&self) -> Option<u16> {
+        self.surface.get_depth_buffer_bits()
+    }
+
+    fn get_stencil_buffer_bits(&self) -> Option<u16> {
+        self.surface.get_stencil_buffer_bits()
+    }
+
+    fn draw<'a, 'b, V, I, U>(&mut self, vertices: V, indices: I, program: &Program, uniforms: &U, params: &DrawParameters<'_>) -> Result<(), DrawError>
+    where
+        V: MultiVerticesSource<'b>,
+        I: Into<IndicesSource<'a>>,
+        U: Uniforms,
+    {
+        let params = DrawParameters {
+            viewport: Some(params.viewport.map_or(self.rect, |rect| self.offset(&rect))),
+            scissor: Some(params.scissor.map_or(self.rect, |rect| self.offset(&rect))),
+            ..params.clone()
+        };
+        self.surface.draw(vertices, indices, program, uniforms, &params)
+    }
+
+    fn blit_buffers_from_frame(&self, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter, mask: BlitMask) {
+        self.surface.blit_buffers_from_frame(source_rect, &self.offset_target(target_rect), filter, mask)
+    }
+
+    fn blit_buffers_from_simple_framebuffer(&self, source: &SimpleFrameBuffer<'_>, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter, mask: BlitMask) {
+        self.surface.blit_buffers_from_simple_framebuffer(source, source_rect, &self.offset_target(target_rect), filter, mask)
+    }
+
+    fn blit_buffers_from_multioutput_framebuffer(&self, source: &MultiOutputFrameBuffer<'_>, source_rect: &Rect, target_rect: &BlitTarget, filter: MagnifySamplerFilter, mask: BlitMask) {
+        self.surface.blit_buffers_from_multioutput_framebuffer(source, source_rect, &self.offset_target(target_rect), filter, mask)
+    }
+
+    fn blit_color<T: Surface>(&self, source_rect: &Rect, target: &T, target_rect: &BlitTarget, filter: MagnifySamplerFilter) {
+        self.surface.blit_color(&self.offset(source_rect), target, target_rect, filter)
+    }
+}
@@ -0,0 +1,190 @@
+use std::{f32::consts::TAU, rc::Rc};
+
+use anyhow::{anyhow, Result};
+use glium::{backend::Facade, draw_parameters::Smooth, Program, Surface};
+
+use crate::{
+    graphics::{aspect_scale, Drawable},
+    polyline::{LineStyle, Polyline},
+    text::TextRenderer,
+    Colour, Complex32, LineVertex,
+};
+
+// Guides reach this far from the origin, which covers the visible plane up to
+// a 4:1 aspect ratio.
+const EXTENT: f32 = 4.0;
+const CIRCLE_SEGMENTS: usize = 256;
+// Length of a tick mark on either side of its axis, in plane units.
+const TICK_LENGTH: f32 = 0.015;
+// Gap between a tick and its label, in pixels.
+const LABEL_GAP: f32 = 4.0;
+
+pub struct Ticks {
+    pub spacing: f32,
+    // Label size in pixels and colour, or `None` for unlabelled ticks.
+    pub labels: Option<(f32, Colour)>,
+}
+
+struct Labels {
+    text: TextRenderer,
+    colour: Colour,
+    // Each label and the tick it belongs to; real ticks are labelled below
+    // the axis and imaginary ticks to its right.
+    ticks: Vec<(String, Complex32)>,
+}
+
+// Axes, a grid and the unit circle in the complex plane, drawn under the lines.
+pub struct Guides<'a> {
+    guides: Vec<Polyline<'a>>,
+    labels: Option<Labels>,
+}
+
+impl<'a> Guides<'a> {
+    pub fn new(
+        facade: &impl Facade,
+        program: Rc<Program>,
+        axes: Option<(LineStyle, Option<Ticks>)>,
+        grid: Option<(LineStyle, f32)>,
+        unit_circle: Option<LineStyle>,
+    ) -> Result<Self> {
+        let mut guides = vec![];
+        let mut labels = None;
+
+        // The grid goes first so the axes and circle are drawn over it.
+        if let Some((style, spacing)) = grid {
+            let mut segments = vec![];
+            for offset in multiples(spacing)? {
+                segments.push([Complex32::new(offset, -EXTENT), Complex32::new(offset, EXTENT)]);
+                segments.push([Complex32::new(-EXTENT, offset), Complex32::new(EXTENT, offset)]);
+            }
+            guides.push(Polyline::lines("Guides", facade, &segment_vertices(&segments), Rc::clone(&program), &style));
+        }
+
+        if let Some((style, ticks)) = axes {
+            let mut segments = vec![
+                [Complex32::new(-EXTENT, 0.0), Complex32::new(EXTENT, 0.0)],
+                [Complex32::new(0.0, -EXTENT), Complex32::new(0.0, EXTENT)],
+            ];
+
+            if let Some(ticks) = ticks {
+                let offsets: Vec<f32> = multiples(ticks.spacing)?.filter(|x| *x != 0.0).collect();
+                for &offset in &offsets {
+                    segments.push([Complex32::new(offset, -TICK_LENGTH), Complex32::new(offset, TICK_LENGTH)]);
+                    segments.push([Complex32::new(-TICK_LENGTH, offset), Complex32::new(TICK_LENGTH, offset)]);
+                }
+
+                if let Some((size, colour)) = ticks.labels {
+                    labels = Some(Labels {
+                        text: TextRenderer::new(facade, size)?,
+                        colour,
+                        ticks: offsets
+                            .iter()
+                            .flat_map(|&offset| {
+                                let value = (offset * 1e4).round() / 1e4;
+                                [
+                                    (format!("{value}"), Complex32::new(offset, 0.0)),
+                                    (format!("{value}i"), Complex32::new(0.0, offset)),
+                                ]
+                            })
+                            .collect(),
+                    });
+                }
+            }
+            guides.push(Polyline::lines("Guides", facade, &segment_vertices(&segments), Rc::clone(&program), &style));
+        }
+
+        if let Some(style) = unit_circle {
+            // Laid out along the whole circumference so dashes run continuously.
+            let step = TAU / CIRCLE_SEGMENTS as f32;
+            let vertices: Vec<LineVertex> = (0..CIRCLE_SEGMENTS)
+                .flat_map(|i| {
+                    [i, i + 1].map(|j| LineVertex {
+                        distance: j as f32 * step,
+                        ..Complex32::cis(j as f32 * step).into()
+                    })
+                })
+                .collect();
+            guides.push(Polyline::lines("Guides", facade, &vertices, program, &style));
+        }
+
+        Ok(Self { guides, labels })
+    }
+
+    pub fn set_line_hints(&mut self, smooth: Option<Smooth>, width_scale: f32) {
+        for guide in &mut self.guides {
+            guide.set_line_hints(smooth, width_scale);
+        }
+    }
+
+    pub fn draw<S: Surface>(&mut self, surface: &mut S) {
+        let scale = aspect_scale(surface);
+
+        for guide in &mut self.guides {
+            guide.set_scale(scale);
+            guide.draw(surface).unwrap();
+        }
+
+        let Some(labels) = &self.labels else {
+            return;
+        };
+        let (width, height) = surface.get_dimensions();
+        let (width, height) = (width as f32, height as f32);
+        for (label, tick) in &labels.ticks {
+            let x = (tick.re * scale[0] + 1.0) * 0.5 * width;
+            let y = (1.0 - tick.im * scale[1]) * 0.5 * height;
+            let size = [labels.text.width(label), labels.text.line_height()];
+            let position = match tick.im == 0.0 {
+                true => [x - size[0] * 0.5, y + LABEL_GAP],
+                false => [x + LABEL_GAP, y - size[1] * 0.5],
+            };
+
+            // Labels that would be cut off at the edges are left out.
+            if position[0] < 0.0 || position[1] < 0.0 || position[0] + size[0] > width || position[1] + size[1] > height {
+                continue;
+            }
+            labels.text.draw(surface, label, position, labels.colour).unwrap();
+        }
+    }
+}
+
+// Each segment is dashed from its own start, so that dashes on parallel
+// grid lines line up.
+fn segment_vertices(segments: &[[Complex32; 2]]) -> Vec<LineVertex> {
+    segments
+        .iter()
+        .flat_map(|[start, end]| {
+            [
+                LineVertex::from(*start),
+                LineVertex {
+                    distance: (end - start).norm(),
+                    ..(*end).into()
+                },
+            ]
+        })
+        .collect()
+}
+
+// Every multiple of `spacing` within the guides' extent, including zero.
+fn multiples(spacing: f32) -> Result<impl Iterator<Item = f32>> {
+    if spacing <= 0.0 || EXTENT / spacing > 1000.0 {
+        return Err(anyhow!("Expected a guide spacing between {} and {EXTENT}, got {spacing}", EXTENT / 1000.0));
+    }
+
+    let count = (EXTENT / spacing).floor() as i32;
+    Ok((-count..=count).map(move |i| i as f32 * spacing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiples_cover_the_extent_symmetrically() {
+        let values: Vec<f32> = multiples(1.5).unwrap().collect();
+        assert_eq!(values, [-3.0, -1.5, 0.0, 1.5, 3.0]);
+
+        assert!(multiples(0.0).is_err());
+        assert!(multiples(-0.5).is_err());
+        assert!(multiples(0.001).is_err());
+    }
+}
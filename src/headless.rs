@@ -0,0 +1,88 @@
+use std::{ffi::CString, num::NonZeroU32, os::raw::c_void, rc::Rc};
+
+use glium::{
+    backend::{Backend, Context},
+    glutin::{
+        api::egl::{
+            context::PossiblyCurrentContext, device::Device, display::Display,
+            surface::Surface as EglSurface,
+        },
+        config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+        context::{ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentGlContext},
+        display::GlDisplay,
+        surface::{PbufferSurface, SurfaceAttributesBuilder},
+    },
+    SwapBuffersError,
+};
+
+struct PbufferBackend {
+    context: PossiblyCurrentContext,
+    surface: EglSurface<PbufferSurface>,
+    display: Display,
+    dimensions: (u32, u32),
+}
+
+unsafe impl Backend for PbufferBackend {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
+        Ok(())
+    }
+
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        self.display.get_proc_address(&CString::new(symbol).unwrap())
+    }
+
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn resize(&self, _new_size: (u32, u32)) {}
+
+    fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    unsafe fn make_current(&self) {
+        self.context.make_current(&self.surface).unwrap();
+    }
+}
+
+// Creates an offscreen context backed by a pbuffer of the given size, so that
+// `Frame::new(context, (width, height))` renders exactly like a window would.
+pub fn context(width: u32, height: u32) -> Rc<Context> {
+    let device = Device::query_devices()
+        .expect("Unable to query EGL devices")
+        .next()
+        .expect("No EGL device available for headless rendering");
+
+    let display = unsafe { Display::with_device(&device, None) }.unwrap();
+
+    let template = ConfigTemplateBuilder::new()
+        .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+        .build();
+    let config = unsafe { display.find_configs(template) }
+        .unwrap()
+        .next()
+        .expect("No pbuffer EGL config available");
+
+    let surface = unsafe {
+        display.create_pbuffer_surface(
+            &config,
+            &SurfaceAttributesBuilder::<PbufferSurface>::new()
+                .build(NonZeroU32::new(width).unwrap(), NonZeroU32::new(height).unwrap()),
+        )
+    }
+    .unwrap();
+
+    let context = unsafe { display.create_context(&config, &ContextAttributesBuilder::new().build(None)) }
+        .unwrap()
+        .make_current(&surface)
+        .unwrap();
+
+    let backend = PbufferBackend {
+        context,
+        surface,
+        display,
+        dimensions: (width, height),
+    };
+    unsafe { Context::new(backend, true, Default::default()) }.unwrap()
+}
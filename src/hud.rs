@@ -0,0 +1,168 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use glium::{backend::Facade, Surface};
+
+use crate::{text::TextRenderer, Colour, FourierSet};
+
+// Distance of the text from the edges of the surface, in pixels.
+const MARGIN: f32 = 8.0;
+// Weight of the newest frame in the smoothed frame rate.
+const FPS_SMOOTHING: f32 = 0.1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HudField {
+    Time,
+    Loops,
+    Harmonics,
+    Fps,
+    Names,
+}
+
+impl std::str::FromStr for HudField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "time" => Ok(HudField::Time),
+            "loops" => Ok(HudField::Loops),
+            "harmonics" => Ok(HudField::Harmonics),
+            "fps" => Ok(HudField::Fps),
+            "names" => Ok(HudField::Names),
+            other => Err(anyhow!("Unknown hud field {other}, expected time, loops, harmonics, fps or names")),
+        }
+    }
+}
+
+// Values shown by the HUD for one frame.
+struct Stats<'s> {
+    t: f32,
+    loops: u32,
+    fps: f32,
+    // Name and highest harmonic of every line, sorted by name.
+    lines: Vec<(&'s str, u32)>,
+}
+
+// Status text in the top left corner and a caption in the bottom left.
+pub struct Hud {
+    text: TextRenderer,
+    fields: Vec<HudField>,
+    caption: Option<String>,
+    colour: Colour,
+    visible: bool,
+
+    t: f32,
+    loops: u32,
+    last_frame: Option<Instant>,
+    fps: f32,
+}
+
+impl Hud {
+    pub fn new(
+        facade: &impl Facade,
+        size: f32,
+        colour: Colour,
+        fields: Vec<HudField>,
+        caption: Option<String>,
+        visible: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            text: TextRenderer::new(facade, size)?,
+            fields,
+            caption,
+            colour,
+            visible,
+            t: 0.0,
+            loops: 0,
+            last_frame: None,
+            fps: 0.0,
+        })
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Called once per frame. `t` running backwards means the animation looped.
+    pub fn update(&mut self, t: f32) {
+        if t < self.t {
+            self.loops += 1;
+        }
+        self.t = t;
+
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let fps = 1.0 / (now - last_frame).as_secs_f32().max(f32::EPSILON);
+            self.fps = match self.fps > 0.0 {
+                true => self.fps + (fps - self.fps) * FPS_SMOOTHING,
+                false => fps,
+            };
+        }
+        self.last_frame = Some(now);
+    }
+
+    pub fn draw<S: Surface>(&self, surface: &mut S, lines: &[FourierSet]) {
+        if !self.visible {
+            return;
+        }
+
+        let mut lines: Vec<_> = lines.iter().map(|line| (line.name.as_str(), line.harmonics())).collect();
+        lines.sort();
+        let stats = Stats {
+            t: self.t,
+            loops: self.loops,
+            fps: self.fps,
+            lines,
+        };
+
+        let status = rows(&self.fields, &stats).join("\n");
+        self.text.draw(surface, &status, [MARGIN, MARGIN], self.colour).unwrap();
+
+        if let Some(caption) = &self.caption {
+            let height = surface.get_dimensions().1 as f32;
+            let top = height - MARGIN - caption.lines().count() as f32 * self.text.line_height();
+            self.text.draw(surface, caption, [MARGIN, top], self.colour).unwrap();
+        }
+    }
+}
+
+fn rows(fields: &[HudField], stats: &Stats) -> Vec<String> {
+    fields
+        .iter()
+        .flat_map(|field| match field {
+            HudField::Time => vec![format!("t = {:.3}", stats.t)],
+            HudField::Loops => vec![format!("loop {}", stats.loops)],
+            HudField::Harmonics => {
+                let harmonics: Vec<_> = stats.lines.iter().map(|(_, h)| h.to_string()).collect();
+                vec![format!("harmonics {}", harmonics.join(", "))]
+            }
+            HudField::Fps => vec![format!("{:.1} fps", stats.fps)],
+            HudField::Names => stats.lines.iter().map(|(name, _)| name.to_string()).collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_follow_the_field_order() {
+        let stats = Stats {
+            t: 0.25,
+            loops: 3,
+            fps: 59.94,
+            lines: vec![("line1", 50), ("line2", 12)],
+        };
+        let fields: Vec<HudField> = ["names", "time", "harmonics", "fps", "loops"]
+            .into_iter()
+            .map(|x| x.parse().unwrap())
+            .collect();
+
+        assert_eq!(
+            rows(&fields, &stats),
+            ["line1", "line2", "t = 0.250", "harmonics 50, 12", "59.9 fps", "loop 3"]
+        );
+        assert!("speed".parse::<HudField>().is_err());
+    }
+}
@@ -1,36 +1,62 @@
-#![feature(fn_traits)]
-#![feature(iter_collect_into)]
-#![feature(vec_push_within_capacity)]
-
-#[macro_use]
-extern crate glium;
-
 use std::{
     process::exit,
     thread::sleep,
     time::{Duration, Instant},
 };
 
-mod bar_vertex;
-use bar_vertex::BarVertex;
+mod polyline;
+use polyline::LineVertex;
 
-mod outline_vertex;
-use outline_vertex::OutlineVertex;
+mod markers;
 
 mod colour;
 use colour::Colour;
 
 mod parsing;
-use parsing::IniData;
+use parsing::{parse_args, IniData, WindowSetup};
 
 mod fourier;
 use fourier::FourierSet;
 
+mod gpu_bar;
+
+mod supersample;
+
+mod render_target;
+
+mod text;
+
+mod hud;
+
+mod guides;
+
+mod spectrum;
+
+mod traces;
+
+mod signal;
+
+mod expr;
+
+mod shapes;
+
+mod contour;
+
+mod glyphs;
+
+#[cfg(test)]
+mod headless;
+
+#[cfg(test)]
+mod golden;
+
 mod graphics;
 
 mod buffer;
 
-use glium::{backend::glutin::SimpleWindowBuilder, winit::event_loop::EventLoop, Surface};
+mod window;
+
+use glium::winit::{event_loop::EventLoop, keyboard::Key};
 
 use num::complex::Complex32;
 
@@ -38,24 +64,24 @@ fn main() {
     let program_start = Instant::now();
     let event_loop = EventLoop::new().unwrap();
 
-    let (window, facade) = SimpleWindowBuilder::new()
-        .with_title("Fourier Series Visualiser")
-        .with_inner_size(720, 720)
-        .build(&event_loop);
+    let overrides = parse_args(std::env::args().skip(1)).unwrap();
+    let window_setup = WindowSetup::parse_ini("data.ini", &overrides).unwrap();
 
-    let mut args = IniData::parse_ini("data.ini", &facade).unwrap();
-
-    let mut t = 0_f32;
+    let (window, facade) = window::build(&event_loop, &window_setup).unwrap();
 
-    let inner_samples = args.fps * args.time - 1.0;
+    let mut args = IniData::parse_ini("data.ini", &facade).unwrap();
 
     let target_ft = Duration::from_secs_f32(1.0 / (args.fps));
 
     let mut prev_frame = Instant::now();
 
-    let mut iters = 0_u64;
+    // Each period draws t = 0, step, ..., 1. Saved renders stop after one
+    // period so every frame is written once; otherwise a second is timed too.
+    let frames_per_period = (args.fps * args.time).round() as u64;
+    let periods = if args.saves_frames() { 1 } else { 2 };
+    let mut frame = 0_u64;
 
-    println!("Rendering {} animated frames, {} total outline positions and {} intermediate bar positions.", inner_samples, args.lines.iter().map(|l| l.outline_buffer.size).reduce(|a, b| a + b).unwrap(), args.lines.iter().map(|l| l.outline_buffer.size * l.bars.len()).reduce(|a, b| a + b).unwrap());
+    println!("Rendering {} animated frames, {} total outline positions and {} phasors per frame.", frames_per_period, args.lines.iter().map(|l| l.outline_buffer.size).reduce(|a, b| a + b).unwrap(), args.lines.iter().map(|l| l.bars.len()).reduce(|a, b| a + b).unwrap());
     let render_start = Instant::now();
 
     #[allow(deprecated)]
@@ -68,26 +94,21 @@ fn main() {
                 glium::winit::event::WindowEvent::RedrawRequested => {
                     let mut target = facade.draw();
 
-                    t = if t > 1.0 {
-                        iters += 1;
-                        0.0
-                    } else {
-                        t + 1.0 / (inner_samples as f32)
-                    };
-
-                    target.clear_color(
-                        args.bg_colour.r,
-                        args.bg_colour.g,
-                        args.bg_colour.b,
-                        args.bg_colour.a,
-                    );
-
-                    for item in &mut args.lines {
-                        item.draw(&mut target, t);
+                    let t = ((frame % frames_per_period) as f32 * args.step).min(1.0);
+                    frame += 1;
+
+                    if let Err(e) = args.draw(&mut target, t) {
+                        eprintln!("Unable to draw the frame at t = {t}: {e}");
+                        exit(1);
                     }
 
                     target.finish().unwrap();
                 }
+                glium::winit::event::WindowEvent::KeyboardInput { event, .. }
+                    if event.state.is_pressed() && !event.repeat && event.logical_key == Key::Character("h".into()) =>
+                {
+                    args.toggle_hud();
+                }
                 glium::winit::event::WindowEvent::Resized(window_size) => {
                     facade.resize(window_size.into());
                 }
@@ -103,7 +124,7 @@ fn main() {
                     window.request_redraw();
                     prev_frame = now;
                 } else {
-                    if iters > 1 {
+                    if frame >= periods * frames_per_period {
                         let now = Instant::now();
                         println!(
                             "Time elapsed since program start: {}",
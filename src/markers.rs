@@ -0,0 +1,228 @@
+use std::rc::Rc;
+
+use glium::{
+    backend::Facade,
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    uniforms::{AsUniformValue, UniformValue, Uniforms},
+    Program, Surface, VertexBuffer,
+};
+
+use crate::{
+    graphics::{aspect_scale, DrawItem, Drawable},
+    Colour, Complex32,
+};
+
+// A marker vertex sits at `position` and is pushed `corner` pixels along and
+// across `direction` on screen, so markers keep their size at any zoom.
+#[derive(Copy, Clone, Default)]
+pub struct MarkerVertex {
+    pub position: [f32; 2],
+    pub direction: [f32; 2],
+    pub corner: [f32; 2],
+}
+
+implement_vertex!(MarkerVertex, position, direction, corner);
+
+impl std::fmt::Display for MarkerVertex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.position[0], self.position[1])
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct MarkerStyle {
+    pub colour: Colour,
+    // In pixels: the length of an arrowhead or the diameter of a dot.
+    pub size: f32,
+}
+
+pub struct MarkerUniform {
+    colour: Colour,
+    size: f32,
+    round: bool,
+    scale: [f32; 2],
+    viewport: [f32; 2],
+}
+
+impl Uniforms for MarkerUniform {
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut output: F) {
+        output("vertex_colour", self.colour.as_uniform_value());
+        output("size", UniformValue::Float(self.size));
+        output("round", UniformValue::Bool(self.round));
+        output("scale", UniformValue::Vec2(self.scale));
+        output("viewport", UniformValue::Vec2(self.viewport));
+    }
+}
+
+enum MarkerKind {
+    Arrows,
+    Joints,
+    Tip,
+}
+
+struct MarkerSet<'a> {
+    kind: MarkerKind,
+    draw_item: DrawItem<'a, MarkerVertex, MarkerUniform>,
+    size: f32,
+}
+
+// Arrowheads at each phasor tip, dots at the joints between phasors and a
+// marker at the pen tip, each optional.
+pub struct Markers<'a> {
+    sets: Vec<MarkerSet<'a>>,
+}
+
+impl<'a> Markers<'a> {
+    // `bars` is the number of phasors, including the constant term that the
+    // drawn chain starts from.
+    pub fn new(
+        facade: &impl Facade,
+        bars: usize,
+        program: Rc<Program>,
+        arrows: Option<MarkerStyle>,
+        joints: Option<MarkerStyle>,
+        tip: Option<MarkerStyle>,
+    ) -> Self {
+        let segments = bars.saturating_sub(1);
+        let sets = [
+            (MarkerKind::Arrows, arrows, 3 * segments, PrimitiveType::TrianglesList),
+            (MarkerKind::Joints, joints, segments, PrimitiveType::Points),
+            (MarkerKind::Tip, tip, 1, PrimitiveType::Points),
+        ]
+        .into_iter()
+        .filter(|(_, _, len, _)| *len > 0)
+        .filter_map(|(kind, style, len, primitive)| {
+            let style = style?;
+            let round = matches!(primitive, PrimitiveType::Points);
+            let mut draw_item = DrawItem::new(
+                "Markers",
+                VertexBuffer::empty_dynamic(facade, len).unwrap(),
+                NoIndices(primitive),
+                Rc::clone(&program),
+                MarkerUniform {
+                    colour: style.colour,
+                    size: style.size,
+                    round,
+                    scale: [1.0, 1.0],
+                    viewport: [1.0, 1.0],
+                },
+            );
+            draw_item.params_mut().point_size = Some(style.size);
+            Some(MarkerSet {
+                kind,
+                draw_item,
+                size: style.size,
+            })
+        })
+        .collect();
+
+        Self { sets }
+    }
+
+    // `size_scale` multiplies every marker size, for surfaces with more
+    // pixels than the screen.
+    pub fn set_size_scale(&mut self, size_scale: f32) {
+        for set in &mut self.sets {
+            let size = set.size * size_scale;
+            set.draw_item.uniforms_mut().size = size;
+            set.draw_item.params_mut().point_size = Some(size);
+        }
+    }
+
+    // `positions` are the joints of the bar chain, ending at the pen tip.
+    pub fn update(&mut self, positions: &[Complex32]) {
+        for set in &mut self.sets {
+            let vertices: Vec<MarkerVertex> = match set.kind {
+                MarkerKind::Arrows => positions
+                    .windows(2)
+                    .flat_map(|pair| {
+                        let direction = pair[1] - pair[0];
+                        [[0.0, 0.0], [-1.0, 0.4], [-1.0, -0.4]].map(|corner| MarkerVertex {
+                            position: [pair[1].re, pair[1].im],
+                            direction: [direction.re, direction.im],
+                            corner,
+                        })
+                    })
+                    .collect(),
+                MarkerKind::Joints => positions[..positions.len().saturating_sub(1)]
+                    .iter()
+                    .map(|p| MarkerVertex {
+                        position: [p.re, p.im],
+                        ..Default::default()
+                    })
+                    .collect(),
+                MarkerKind::Tip => positions
+                    .last()
+                    .map(|p| MarkerVertex {
+                        position: [p.re, p.im],
+                        ..Default::default()
+                    })
+                    .into_iter()
+                    .collect(),
+            };
+            set.draw_item.upload_prefix(&vertices);
+        }
+    }
+
+    pub fn draw<S: Surface>(&mut self, surface: &mut S) {
+        let scale = aspect_scale(surface);
+        let (width, height) = surface.get_dimensions();
+
+        for set in &mut self.sets {
+            let uniforms = set.draw_item.uniforms_mut();
+            uniforms.scale = scale;
+            uniforms.viewport = [width as f32, height as f32];
+            set.draw_item.draw(surface).unwrap();
+        }
+    }
+}
+
+pub fn get_program(facade: &impl Facade) -> Program {
+    let vertex_shader_src = r#"
+            #version 140
+
+            uniform vec2 scale;
+            uniform vec2 viewport;
+            uniform float size;
+
+            in vec2 position;
+            in vec2 direction;
+            in vec2 corner;
+
+            void main() {
+                vec2 pos = position * scale;
+                vec2 screen = direction * scale * viewport * 0.5;
+                float len = length(screen);
+
+                if (len > 0.0) {
+                    // Arrowheads never take up more than half of a short bar.
+                    float head = min(size, 0.5 * len);
+                    vec2 along = screen / len;
+                    vec2 across = vec2(-along.y, along.x);
+                    vec2 offset = (along * corner.x + across * corner.y) * head;
+                    pos += offset * 2.0 / viewport;
+                }
+
+                gl_Position = vec4(pos, 0.0, 1.0);
+            }
+        "#;
+
+    let fragment_shader_src = r#"
+            #version 140
+
+            uniform vec4 vertex_colour;
+            uniform bool round;
+
+            out vec4 color;
+
+            void main() {
+                if (round && length(gl_PointCoord - vec2(0.5)) > 0.5) {
+                    discard;
+                }
+                color = vertex_colour;
+            }
+        "#;
+
+    Program::from_source(facade, vertex_shader_src, fragment_shader_src, None).unwrap()
+}
@@ -1,41 +1,177 @@
-use std::sync::Arc;
+use std::{cmp::Ordering, collections::HashMap, path::{Path, PathBuf}, rc::Rc};
 
 use anyhow::{anyhow, Result, Ok};
-use ini::ini;
+use ab_glyph::FontVec;
+use ini::configparser::ini::Ini;
 
-use crate::{bar_vertex, outline_vertex, Colour, Complex32, FourierSet};
+use crate::{colour::Gradient, contour::{self, Bitmap}, fourier::{self, BarShading, Route}, glyphs, gpu_bar, graphics::Viewport, guides::{Guides, Ticks}, hud::{Hud, HudField}, markers::{self, MarkerStyle, Markers}, polyline::{self, AlphaMode, LineStyle}, expr::Expression, render_target::RenderTarget, shapes::Shape, spectrum::{self, Spectrum, SpectrumStyle}, supersample::Supersampler, signal::{self, Waveform}, text, traces::{ProjectionTraces, Strips, TraceLayout}, Colour, Complex32, FourierSet};
 
-use glium::{glutin::surface::WindowSurface, Program};
+use glium::{backend::Facade, draw_parameters::Smooth, Program, Surface};
 
 pub struct IniData<'a> {
     pub bg_colour: Colour,
     pub fps: f32,
     pub time: f32,
+    pub step: f32,
     pub lines: Vec<FourierSet<'a>>,
     pub render: bool,
+    supersampler: Option<Supersampler>,
+    render_target: Option<RenderTarget>,
+    hud: Option<Hud>,
+    guides: Option<Guides<'a>>,
+    traces: Option<TraceLayout>,
 
-    _bar_program: Arc<Program>,
-    _outline_program: Arc<Program>,
+    _bar_program: Rc<Program>,
+    _gpu_bar_program: Option<Rc<Program>>,
+    _outline_program: Rc<Program>,
+    _marker_program: Rc<Program>,
+    _spectrum_program: Rc<Program>,
 }
 
+// Settings needed before the window exists, read ahead of `IniData`.
+pub struct WindowSetup {
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub resizable: bool,
+    pub transparent: bool,
+    pub multisampling: u8,
+}
+
+// Setup keys that may also be given on the command line, and whether each is
+// a flag that can appear without a value.
+const WINDOW_KEYS: [(&str, bool); 8] = [
+    ("width", false),
+    ("height", false),
+    ("title", false),
+    ("fullscreen", true),
+    ("vsync", true),
+    ("resizable", true),
+    ("transparent", true),
+    ("multisampling", false),
+];
+
+type Config = HashMap<String, HashMap<String, Option<String>>>;
+
 macro_rules! get_expect {
     ($hash:ident, $($name:literal),+) => {
         ($($hash.get($name).map(|x| x.as_deref()).flatten().ok_or(anyhow!("Unable to find key {}", $name))?,)+)
     };
 }
 
+macro_rules! get_optional {
+    ($hash:ident, $($name:literal),+) => {
+        ($($hash.get($name).map(|x| x.as_deref()).flatten(),)+)
+    };
+}
+
+fn read_config(path: &str) -> Result<Config> {
+    // `#` also starts hex colours, so it only marks a comment at the start of a line.
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Unable to read {path}: {e}"))?
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("#"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut config = Ini::new();
+    config.set_comment_symbols(&[';']);
+    config.read(text).map_err(|e| anyhow!("Unable to parse {path}: {e}"))
+}
+
+fn get_setup(data: &Config) -> Result<&HashMap<String, Option<String>>> {
+    data.get("setup").ok_or(anyhow!("Unable to find the Setup key"))
+}
+
+fn parse_bool(key: &str, value: Option<&str>, default: bool) -> Result<bool> {
+    match value.map(str::trim) {
+        None => Ok(default),
+        Some("yes" | "true") => Ok(true),
+        Some("no" | "false") => Ok(false),
+        Some(other) => Err(anyhow!("Expected {key} to be yes or no, got {other}")),
+    }
+}
+
+// Reads `--key value`, `--key=value` and bare `--flag` arguments for the keys in
+// `WINDOW_KEYS`, which then take precedence over the same keys in `[setup]`.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Vec<(String, String)>> {
+    let mut args = args.into_iter().peekable();
+    let mut overrides = vec![];
+
+    while let Some(arg) = args.next() {
+        let arg = arg
+            .strip_prefix("--")
+            .ok_or(anyhow!("Unexpected argument {arg}, expected --key value"))?;
+        let (key, value) = match arg.split_once("=") {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (arg, None),
+        };
+
+        let &(key, flag) = WINDOW_KEYS
+            .iter()
+            .find(|(k, _)| *k == key)
+            .ok_or(anyhow!("Unknown option --{key}"))?;
+
+        let value = match value {
+            Some(value) => value,
+            None if flag && !args.peek().is_some_and(|next| ["yes", "no", "true", "false"].contains(&next.as_str())) => "yes".to_string(),
+            None => args.next().ok_or(anyhow!("Expected a value after --{key}"))?,
+        };
+        overrides.push((key.to_string(), value));
+    }
+
+    Ok(overrides)
+}
 
+impl WindowSetup {
+    pub fn parse_ini(path: &str, overrides: &[(String, String)]) -> Result<WindowSetup> {
+        let data = read_config(path)?;
+        let mut setup = get_setup(&data)?.clone();
+        for (key, value) in overrides {
+            setup.insert(key.clone(), Some(value.clone()));
+        }
+
+        let (width, height, title) = get_optional!(setup, "width", "height", "title");
+        let width = width.map(|x| x.trim().parse()).transpose()?.unwrap_or(720);
+        let height = height.map(|x| x.trim().parse()).transpose()?.unwrap_or(720);
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Expected a positive window size, got {width}x{height}"));
+        }
+        let title = title.map(str::trim).unwrap_or("Fourier Series Visualiser").to_string();
+
+        let (fullscreen, vsync, resizable, transparent) =
+            get_optional!(setup, "fullscreen", "vsync", "resizable", "transparent");
+
+        let (multisampling,) = get_optional!(setup, "multisampling");
+        let multisampling = multisampling.map(|x| x.trim().parse::<u8>()).transpose()?.unwrap_or(0);
+        if multisampling != 0 && !multisampling.is_power_of_two() {
+            return Err(anyhow!("Expected multisampling to be 0 or a power of two, got {multisampling}"));
+        }
+
+        Ok(WindowSetup {
+            width,
+            height,
+            title,
+            fullscreen: parse_bool("fullscreen", fullscreen, false)?,
+            vsync: parse_bool("vsync", vsync, true)?,
+            resizable: parse_bool("resizable", resizable, true)?,
+            transparent: parse_bool("transparent", transparent, false)?,
+            multisampling,
+        })
+    }
+}
 
 impl<'a> IniData<'a> {
     pub fn parse_ini(
         path: &str,
-        facade: &glium::Display<WindowSurface>,
+        facade: &impl Facade,
     ) -> Result<IniData<'a>> {
-        let data = ini!(path);
-
-        let setup = data
-            .get("setup")
-            .ok_or(anyhow!("Unable to find the Setup key"))?;
+        let data = read_config(path)?;
+        let setup = get_setup(&data)?;
+        // Files named in the config are relative to it.
+        let dir = Path::new(path).parent().unwrap_or(Path::new("."));
 
         let (bg_colour, fps, time, render) =
             get_expect!(setup, "bg_colour", "fps", "time", "render");
@@ -43,30 +179,140 @@ impl<'a> IniData<'a> {
         let bg_colour = bg_colour.parse()?;
         let fps = fps.parse()?;
         let time = time.parse()?;
+        let step = frame_step(fps, time)?;
         let render = render.trim() == "yes";
 
+        let (bar_renderer,) = get_optional!(setup, "bar_renderer");
+        let gpu_bar_program = match bar_renderer.map(str::trim) {
+            None | Some("cpu") => None,
+            Some("gpu") => gpu_bar::get_program(facade)
+                .inspect_err(|e| eprintln!("Unable to build the GPU bar renderer, falling back to the CPU path: {e}"))
+                .ok()
+                .map(Rc::new),
+            Some(other) => return Err(anyhow!("Unknown bar_renderer {other}, expected cpu or gpu")),
+        };
+
+        let (supersampling, smooth_lines) = get_optional!(setup, "supersampling", "smooth_lines");
+        let supersampling = supersampling.map(|x| x.trim().parse::<u32>()).transpose()?.unwrap_or(1);
+        let mut supersampler = match render && supersampling > 1 {
+            true => Some(Supersampler::new(facade, supersampling)?),
+            false => None,
+        };
+        let (render_width, render_height, render_preview, render_output) =
+            get_optional!(setup, "render_width", "render_height", "render_preview", "render_output");
+        let render_target = match (render, render_width, render_height) {
+            (true, Some(width), Some(height)) => Some(RenderTarget::new(
+                facade,
+                width.trim().parse()?,
+                height.trim().parse()?,
+                parse_bool("render_preview", render_preview, true)?,
+                render_output.map(|x| PathBuf::from(x.trim())),
+            )?),
+            (true, None, None) if render_output.is_some() => {
+                return Err(anyhow!("Expected render_width and render_height to be set for render_output"))
+            }
+            (_, None, None) | (false, _, _) => None,
+            _ => return Err(anyhow!("Expected both render_width and render_height to be set")),
+        };
+        // A render's size is known now, so one too large to supersample fails here
+        // rather than on its first frame.
+        if let (Some(supersampler), Some(render_target)) = (&mut supersampler, &render_target) {
+            supersampler.resize(render_target.dimensions())?;
+        }
+
+        let (hud_fields, caption, hud_size, hud_colour, hud_visible) =
+            get_optional!(setup, "hud", "caption", "hud_size", "hud_colour", "hud_visible");
+        let hud = match (hud_fields, caption) {
+            (None, None) => None,
+            _ => Some(Hud::new(
+                facade,
+                hud_size.map(|x| x.trim().parse()).transpose()?.unwrap_or(16.0),
+                hud_colour.map(str::parse).transpose()?.unwrap_or(Colour { r: 1.0, g: 1.0, b: 1.0, a: 1.0 }),
+                hud_fields
+                    .map(|fields| fields.split(",").map(str::parse).collect::<Result<Vec<HudField>>>())
+                    .transpose()?
+                    .unwrap_or_default(),
+                // Captions are a single ini value, so `\n` stands for a line break.
+                caption.map(|x| x.trim().replace("\\n", "\n")),
+                parse_bool("hud_visible", hud_visible, true)?,
+            )?),
+        };
+
+        let (projection_traces, trace_size, trace_length) =
+            get_optional!(setup, "projection_traces", "trace_size", "trace_length");
+        // A real signal is read off its imaginary projection, so that strip is on by default.
+        let has_signal = data
+            .iter()
+            .any(|(key, inner)| key.starts_with("line") && (inner.contains_key("signal") || inner.contains_key("samples_y")));
+        let strips = match projection_traces.map(str::trim) {
+            None if has_signal => Some(Strips::Im),
+            None | Some("no" | "false") => None,
+            Some("yes" | "true" | "both") => Some(Strips::Both),
+            Some("re") => Some(Strips::Re),
+            Some("im") => Some(Strips::Im),
+            Some(other) => return Err(anyhow!("Unknown projection_traces {other}, expected yes, no, both, re or im")),
+        };
+        let traces = strips
+            .map(|strips| {
+                TraceLayout::new(
+                    strips,
+                    trace_size.map(|x| x.trim().parse()).transpose()?.unwrap_or(0.3),
+                    trace_length.map(|x| x.trim().parse()).transpose()?.unwrap_or(1.0),
+                )
+            })
+            .transpose()?;
+
+        let smooth = smooth_lines.is_some_and(|x| x.trim() == "yes").then_some(Smooth::Nicest);
+        // Lines are rasterised in supersampled pixels, so widen them to keep their weight on screen.
+        let width_scale = supersampler.as_ref().map_or(1.0, |s| s.factor() as f32);
+
         let mut output = IniData {
             bg_colour,
             lines: vec![],
             fps,
             time,
+            step,
             render,
-            _bar_program: Arc::new(bar_vertex::get_program(facade)),
-            _outline_program: Arc::new(outline_vertex::get_program(facade)),
+            supersampler,
+            render_target,
+            hud,
+            guides: None,
+            traces,
+            _bar_program: Rc::new(polyline::get_program(facade, true)),
+            _gpu_bar_program: gpu_bar_program,
+            _outline_program: Rc::new(polyline::get_program(facade, false)),
+            _marker_program: Rc::new(markers::get_program(facade)),
+            _spectrum_program: Rc::new(spectrum::get_program(facade)),
         };
 
-        let bar_program = Arc::clone(&output._bar_program);
-        let outline_program = Arc::clone(&output._outline_program);
-        
-        let mut lines = data
-            .iter()
+        let bar_program = Rc::clone(&output._bar_program);
+        let gpu_bar_program = output._gpu_bar_program.clone();
+        let outline_program = Rc::clone(&output._outline_program);
+        let marker_program = Rc::clone(&output._marker_program);
+        let spectrum_program = Rc::clone(&output._spectrum_program);
+
+        if let Some(guides) = data.get("guides") {
+            let mut guides = parse_guides(guides, facade, Rc::clone(&outline_program), width_scale)?;
+            guides.set_line_hints(smooth, width_scale);
+            output.guides = Some(guides);
+        }
+
+        // The ini keeps no section order, so lines are drawn in name order,
+        // which decides what overlaps what.
+        let mut sections: Vec<_> = data.iter().collect();
+        sections.sort_by(|(a, _), (b, _)| section_order(a, b));
+
+        let mut lines = sections
+            .into_iter()
             .filter_map::<_, _>(|(key, inner)| {
                 let bar_program = bar_program.clone();
+                let gpu_bar_program = gpu_bar_program.clone();
                 let outline_program = outline_program.clone();
+                let marker_program = marker_program.clone();
+                let spectrum_program = spectrum_program.clone();
 
                 key.starts_with("line").then(|| {
                         let (
-                            points,
                             samples,
                             outline_colour,
                             outline_width,
@@ -74,49 +320,710 @@ impl<'a> IniData<'a> {
                             bar_width,
                         ) = get_expect!(
                             inner,
-                            "points",
                             "samples",
                             "outline_colour",
                             "outline_width",
                             "bar_colour",
                             "bar_width"
                         );
-                        let outline_colour = outline_colour.parse()?;
+                        let outline_colour: Colour = outline_colour.parse()?;
                         let bar_colour = bar_colour.parse()?;
 
-                        let mut points = points
-                            .trim_start_matches("(")
-                            .trim_end_matches(")")
-                            .split("),(")
-                            .map(|x| {
-                                let mut split = x.split(",").map(|x| Ok(x.parse::<f32>()?));
-                                Ok(Complex32::new(
-                                    split.next().ok_or(anyhow!("Error parsing complex from {x}"))??, 
-                                    split.next().ok_or(anyhow!("Error parsing complex from {x}"))??))
-                            })
-                            .collect::<Result<Vec<_>>>()?;
+                        let (outline_gradient, outline_gradient_speed) =
+                            get_optional!(inner, "outline_gradient", "outline_gradient_speed");
 
-                        let samples = samples.parse()?;
+                        let outline_gradient = outline_gradient.map(parse_gradient).transpose()?;
+                        let outline_gradient_speed = outline_gradient_speed.map(str::parse).transpose()?.unwrap_or(0.0);
 
-                        let outline_width = outline_width.parse()?;
-                        let bar_width = bar_width.parse()?;
+                        let (bar_colourmap, bar_colour_by) =
+                            get_optional!(inner, "bar_colourmap", "bar_colour_by");
 
-                        Ok(FourierSet::new(
-                            &mut points,
-                            samples,
-                            outline_colour,
-                            outline_width,
-                            outline_program,
-                            bar_colour,
-                            bar_width,
-                            bar_program,
-                            facade,
-                        ))
+                        let bar_shading = match bar_colour_by.map(str::trim) {
+                            None | Some("frequency") => BarShading::Frequency,
+                            Some("rotation") => BarShading::Rotation,
+                            Some("amplitude") => BarShading::Amplitude,
+                            Some(other) => return Err(anyhow!("Unknown bar_colour_by {other}, expected frequency, rotation or amplitude")),
+                        };
+                        let bar_colourmap = bar_colourmap
+                            .map(parse_gradient)
+                            .transpose()?
+                            .map(|colourmap| (colourmap, bar_shading));
+
+                        let (ghost_harmonics, ghost_colours) =
+                            get_optional!(inner, "ghost_harmonics", "ghost_colours");
+
+                        let ghost_harmonics = ghost_harmonics
+                            .map(|h| h.split(",").map(|x| Ok(x.trim().parse::<usize>()?)).collect::<Result<Vec<_>>>())
+                            .transpose()?
+                            .unwrap_or_default();
+
+                        let ghost_colours = match ghost_colours {
+                            Some(colours) => parse_colour_list(colours)?,
+                            None => (1..=ghost_harmonics.len())
+                                .map(|i| Colour {
+                                    a: outline_colour.a * i as f32 / (ghost_harmonics.len() + 1) as f32,
+                                    ..outline_colour
+                                })
+                                .collect(),
+                        };
+
+                        if ghost_colours.len() != ghost_harmonics.len() {
+                            return Err(anyhow!(
+                                "Expected {} ghost_colours to match ghost_harmonics, got {}",
+                                ghost_harmonics.len(),
+                                ghost_colours.len()
+                            ));
+                        }
+
+                        let ghosts: Vec<_> = ghost_harmonics.into_iter().zip(ghost_colours).collect();
+                        // `animate_harmonics = from, to` grows the chain from `from` to `to`
+                        // harmonics over each period, or up to every harmonic without `to`.
+                        let (animate_harmonics,) = get_optional!(inner, "animate_harmonics");
+
+                        let mut routes = parse_routes(inner, dir)?;
+                        fourier::normalise(&mut routes);
+
+                        let samples = parse_samples(samples)?;
+
+                        let (outline_blend, bar_blend) = get_optional!(inner, "outline_blend", "bar_blend");
+
+                        let (outline_dash, outline_dash_length, outline_dash_gap) =
+                            get_optional!(inner, "outline_style", "outline_dash_length", "outline_dash_gap");
+                        let (bar_dash, bar_dash_length, bar_dash_gap) =
+                            get_optional!(inner, "bar_style", "bar_dash_length", "bar_dash_gap");
+
+                        let outline_style = LineStyle {
+                            colour: outline_colour,
+                            width: outline_width.trim().parse()?,
+                            alpha_mode: outline_blend.map(str::parse).transpose()?.unwrap_or(AlphaMode::Blend),
+                            dash: parse_dash(outline_dash, outline_dash_length, outline_dash_gap)?,
+                        };
+                        let bar_style = LineStyle {
+                            colour: bar_colour,
+                            width: bar_width.trim().parse()?,
+                            alpha_mode: bar_blend.map(str::parse).transpose()?.unwrap_or(AlphaMode::Blend),
+                            dash: parse_dash(bar_dash, bar_dash_length, bar_dash_gap)?,
+                        };
+
+                        // Sources that trace several contours give a line for each.
+                        let count = routes.len();
+                        routes
+                            .iter()
+                            .enumerate()
+                            .map(|(i, route)| {
+                                let name = if count == 1 { key.clone() } else { format!("{key}.{}", i + 1) };
+                                let mut line = FourierSet::new(
+                                    name,
+                                    route,
+                                    samples,
+                                    step,
+                                    outline_style,
+                                    Rc::clone(&outline_program),
+                                    outline_gradient.clone(),
+                                    outline_gradient_speed,
+                                    &ghosts,
+                                    bar_style,
+                                    Rc::clone(&bar_program),
+                                    bar_colourmap.clone(),
+                                    gpu_bar_program.clone(),
+                                    facade,
+                                );
+                                if let Some(range) = animate_harmonics {
+                                    let (from, to) = parse_harmonic_range(range)?;
+                                    line.set_cutoff_animation(from, to.unwrap_or(u32::MAX));
+                                }
+
+                                let marker = |size: Option<&str>, colour: Option<&str>, default: Colour| {
+                                    size.map(|size| {
+                                        Ok(MarkerStyle {
+                                            size: size.trim().parse()?,
+                                            colour: colour.map(str::parse).transpose()?.unwrap_or(default),
+                                        })
+                                    })
+                                    .transpose()
+                                };
+                                let (arrow_size, arrow_colour, joint_size, joint_colour, tip_size, tip_colour) = get_optional!(
+                                    inner,
+                                    "bar_arrow_size",
+                                    "bar_arrow_colour",
+                                    "joint_size",
+                                    "joint_colour",
+                                    "tip_size",
+                                    "tip_colour"
+                                );
+                                let arrows = marker(arrow_size, arrow_colour, bar_colour)?;
+                                let joints = marker(joint_size, joint_colour, bar_colour)?;
+                                let tip = marker(tip_size, tip_colour, outline_colour)?;
+                                if arrows.is_some() || joints.is_some() || tip.is_some() {
+                                    line.set_markers(Markers::new(facade, line.bars.len(), Rc::clone(&marker_program), arrows, joints, tip));
+                                }
+
+                                let (spectrum, spectrum_size, spectrum_background, spectrum_colour, spectrum_highlight) = get_optional!(
+                                    inner,
+                                    "spectrum",
+                                    "spectrum_size",
+                                    "spectrum_background",
+                                    "spectrum_colour",
+                                    "spectrum_highlight"
+                                );
+                                let (spectrum_scale, spectrum_harmonics) =
+                                    get_optional!(inner, "spectrum_scale", "spectrum_harmonics");
+                                if let Some(corner) = spectrum {
+                                    let style = SpectrumStyle {
+                                        corner: corner.parse()?,
+                                        size: spectrum_size.map(|x| x.trim().parse()).transpose()?.unwrap_or(0.25),
+                                        background: spectrum_background.map(str::parse).transpose()?.unwrap_or(Colour { r: 0.0, g: 0.0, b: 0.0, a: 0.6 }),
+                                        colour: spectrum_colour.map(str::parse).transpose()?.unwrap_or(Colour { a: bar_colour.a * 0.35, ..bar_colour }),
+                                        highlight: spectrum_highlight.map(str::parse).transpose()?.unwrap_or(bar_colour),
+                                        log_scale: match spectrum_scale.map(str::trim) {
+                                            None | Some("linear") => false,
+                                            Some("log") => true,
+                                            Some(other) => return Err(anyhow!("Unknown spectrum_scale {other}, expected linear or log")),
+                                        },
+                                        harmonics: spectrum_harmonics
+                                            .map(|x| x.trim().parse())
+                                            .transpose()?
+                                            .unwrap_or(line.harmonics().min(32)),
+                                    };
+                                    line.set_spectrum(Spectrum::new(facade, &line.bars, Rc::clone(&spectrum_program), style));
+                                }
+
+                                if let Some(layout) = traces {
+                                    let (trace_colour, trace_width) = get_optional!(inner, "trace_colour", "trace_width");
+                                    let style = LineStyle {
+                                        colour: trace_colour.map(str::parse).transpose()?.unwrap_or(outline_style.colour),
+                                        width: trace_width.map(|x| x.trim().parse()).transpose()?.unwrap_or(outline_style.width),
+                                        ..outline_style
+                                    };
+                                    line.set_traces(ProjectionTraces::new(facade, Rc::clone(&outline_program), layout.length, &style));
+                                }
+
+                                line.set_line_hints(smooth, width_scale);
+                                Ok(line)
+                            })
+                            .collect::<Result<Vec<_>>>()
                     })
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
 
         std::mem::swap(&mut output.lines, &mut lines);
         Ok(output)
     }
+
+    pub fn saves_frames(&self) -> bool {
+        self.render_target.as_ref().is_some_and(RenderTarget::saves_frames)
+    }
+
+    pub fn toggle_hud(&mut self) {
+        if let Some(hud) = &mut self.hud {
+            hud.toggle();
+        }
+    }
+
+    pub fn draw<S: Surface>(&mut self, target: &mut S, t: f32) -> Result<()> {
+        for line in &mut self.lines {
+            line.update(t);
+        }
+        if let Some(hud) = &mut self.hud {
+            hud.update(t);
+        }
+
+        let scene = Scene {
+            lines: &mut self.lines,
+            bg_colour: &self.bg_colour,
+            guides: self.guides.as_mut(),
+            traces: self.traces,
+        };
+        let (supersampler, hud) = (self.supersampler.as_mut(), self.hud.as_ref());
+        match &mut self.render_target {
+            Some(render_target) => render_target.draw(target, |surface| draw_scene(scene, supersampler, hud, surface))?,
+            None => draw_scene(scene, supersampler, hud, target)?,
+        }
+        Ok(())
+    }
+}
+
+// The HUD is drawn after supersampling, so its text stays sharp and sized in
+// output pixels, but before the render target is shown so renders include it.
+fn draw_scene<S: Surface>(mut scene: Scene, supersampler: Option<&mut Supersampler>, hud: Option<&Hud>, target: &mut S) -> Result<()> {
+    match supersampler {
+        Some(supersampler) => supersampler.draw(target, |surface| draw_lines(&mut scene, surface))?,
+        None => draw_lines(&mut scene, target),
+    }
+    if let Some(hud) = hud {
+        hud.draw(target, scene.lines);
+    }
+    Ok(())
+}
+
+// Everything drawn at the scene's resolution, which may be supersampled.
+struct Scene<'s, 'a> {
+    lines: &'s mut [FourierSet<'a>],
+    bg_colour: &'s Colour,
+    guides: Option<&'s mut Guides<'a>>,
+    traces: Option<TraceLayout>,
+}
+
+fn draw_lines<S: Surface>(scene: &mut Scene, target: &mut S) {
+    let bg_colour = scene.bg_colour;
+    target.clear_color(bg_colour.r, bg_colour.g, bg_colour.b, bg_colour.a);
+
+    match scene.traces {
+        Some(layout) => {
+            let regions = layout.regions(target.get_dimensions());
+            draw_canvas(scene, &mut Viewport::new(target, regions.canvas));
+            for item in scene.lines.iter_mut() {
+                item.draw_traces(target, &regions);
+            }
+        }
+        None => draw_canvas(scene, target),
+    }
+    for item in scene.lines.iter_mut() {
+        item.draw_spectrum(target);
+    }
+}
+
+// The complex plane, which fills the scene unless traces take part of it.
+fn draw_canvas<S: Surface>(scene: &mut Scene, target: &mut S) {
+    if let Some(guides) = &mut scene.guides {
+        guides.draw(target);
+    }
+    for item in scene.lines.iter_mut() {
+        item.draw(target);
+    }
+}
+
+// A line is drawn from exactly one of `points`, a named `signal` with its
+// number of `harmonics`, the real samples in `samples_y`, a generated `shape`,
+// which includes curves given by `x` and `y` or `z` expressions, the contours
+// traced from an `image` or the glyph outlines of some `text`. The last two
+// may give several routes.
+fn parse_routes(section: &HashMap<String, Option<String>>, dir: &Path) -> Result<Vec<Route>> {
+    let (points, waveform, harmonics, samples_y, shape) =
+        get_optional!(section, "points", "signal", "harmonics", "samples_y", "shape");
+    let (image, text, count) = get_optional!(section, "image", "text", "points_count");
+    let count = count.map(|x| x.trim().parse()).transpose()?.unwrap_or(256);
+    if count < 3 {
+        return Err(anyhow!("Expected a points_count of at least 3, got {count}"));
+    }
+
+    // `shape = expr` may be left out when the expressions are given.
+    let shape = match (shape.map(str::trim), parse_curve(section)?) {
+        (Some("expr"), None) => return Err(anyhow!("Expected x and y, or z, for shape expr")),
+        (Some("expr") | None, curve) => curve,
+        (Some(shape), None) => Some(shape.parse::<Shape>()?),
+        (Some(shape), Some(_)) => return Err(anyhow!("Expected either shape {shape} or expressions, not both")),
+    };
+
+    match (points, waveform, samples_y, shape, image, text) {
+        (Some(points), None, None, None, None, None) => Ok(vec![Route::from(points
+            .trim_start_matches("(")
+            .trim_end_matches(")")
+            .split("),(")
+            .map(|x| {
+                let mut split = x.split(",").map(|x| Ok(x.parse::<f32>()?));
+                Ok(Complex32::new(
+                    split.next().ok_or(anyhow!("Error parsing complex from {x}"))??,
+                    split.next().ok_or(anyhow!("Error parsing complex from {x}"))??))
+            })
+            .collect::<Result<Vec<_>>>()?)]),
+        (None, Some(waveform), None, None, None, None) => {
+            let harmonics = harmonics.map(|x| x.trim().parse()).transpose()?.unwrap_or(15);
+            if harmonics == 0 {
+                return Err(anyhow!("Expected at least one harmonic for signal {waveform}"));
+            }
+            Ok(vec![waveform.parse::<Waveform>()?.points(harmonics).into()])
+        }
+        (None, None, Some(samples), None, None, None) => {
+            let samples = samples.split(",").map(|x| Ok(x.trim().parse()?)).collect::<Result<Vec<f32>>>()?;
+            Ok(vec![signal::signal_points(&samples).into()])
+        }
+        (None, None, None, Some(shape), None, None) => Ok(vec![shape.points(count)?.into()]),
+        (None, None, None, None, Some(image), None) => parse_image(section, &dir.join(image.trim()), count),
+        (None, None, None, None, None, Some(text)) => parse_text(section, text, dir, count),
+        (None, None, None, None, None, None) => Err(anyhow!("Unable to find key points, signal, samples_y, shape, image or text")),
+        _ => Err(anyhow!("Expected only one of points, signal, samples_y, shape, image and text")),
+    }
+}
+
+// The largest of the image's contours, or as many as `image_contours` asks
+// for, each evenly resampled to `count` points. With `join` they're traced
+// as one route instead.
+fn parse_image(section: &HashMap<String, Option<String>>, path: &Path, count: usize) -> Result<Vec<Route>> {
+    let (threshold, invert, contours, simplify, join) =
+        get_optional!(section, "image_threshold", "image_invert", "image_contours", "image_simplify", "join");
+    let threshold = threshold.map(|x| x.trim().parse()).transpose()?.unwrap_or(0.5);
+    let tolerance = simplify.map(|x| x.trim().parse()).transpose()?.unwrap_or(0.0);
+
+    let mut traced = Bitmap::load(path, threshold, parse_bool("image_invert", invert, false)?)?.contours();
+    if traced.is_empty() {
+        return Err(anyhow!("Unable to find any shapes in {} at image_threshold {threshold}", path.display()));
+    }
+    match contours.map(str::trim) {
+        Some("all") => (),
+        Some(n) => match n.parse()? {
+            0 => return Err(anyhow!("Expected image_contours to be all or at least 1, got 0")),
+            n => traced.truncate(n),
+        },
+        None => traced.truncate(1),
+    }
+
+    if tolerance > 0.0 {
+        traced = traced.iter().map(|contour| contour::simplify(contour, tolerance)).collect();
+    }
+    if parse_bool("join", join, false)? {
+        return Ok(vec![joined_route(&traced, count)]);
+    }
+    Ok(traced.iter().map(|contour| contour::resample(contour, count).into()).collect())
+}
+
+// A route for each glyph, or one for the whole text with `join`, drawn with
+// the embedded font unless a `font` file is given.
+fn parse_text(section: &HashMap<String, Option<String>>, text: &str, dir: &Path, count: usize) -> Result<Vec<Route>> {
+    let (font, join) = get_optional!(section, "font", "join");
+    let text = text.trim();
+    let text = text.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(text);
+
+    let font = match font {
+        Some(font) => {
+            let path = dir.join(font.trim());
+            let data = std::fs::read(&path).map_err(|e| anyhow!("Unable to read {}: {e}", path.display()))?;
+            FontVec::try_from_vec(data).map_err(|e| anyhow!("Unable to load {}: {e}", path.display()))?
+        }
+        None => FontVec::try_from_vec(text::FONT.to_vec())?,
+    };
+
+    let glyphs = glyphs::text_contours(&font, text);
+    if glyphs.is_empty() {
+        return Err(anyhow!("Unable to find any glyph outlines in text {text}"));
+    }
+    match parse_bool("join", join, false)? {
+        true => Ok(vec![joined_route(&glyphs.concat(), count)]),
+        false => Ok(glyphs.iter().map(|contours| joined_route(contours, count)).collect()),
+    }
+}
+
+// Contours traced as one route, with hidden travel between them.
+fn joined_route(contours: &[Vec<Complex32>], count: usize) -> Route {
+    let mut route = contour::join(contours);
+    route.points = contour::resample(&route.points, count);
+    route
+}
+
+fn parse_curve(section: &HashMap<String, Option<String>>) -> Result<Option<Shape>> {
+    let (x, y, z) = get_optional!(section, "x", "y", "z");
+    let parse = |key: &str, s: &str| s.parse::<Expression>().map_err(|e| anyhow!("Unable to parse {key}: {e}"));
+
+    match (x, y, z) {
+        (None, None, None) => Ok(None),
+        (Some(x), Some(y), None) => Ok(Some(Shape::Cartesian(parse("x", x)?, parse("y", y)?))),
+        (None, None, Some(z)) => Ok(Some(Shape::Parametric(parse("z", z)?))),
+        _ => Err(anyhow!("Expected both x and y, or z on its own")),
+    }
+}
+
+// Splits a comma separated list, ignoring commas nested inside parentheses,
+// and unwraps items that are entirely parenthesised, so that
+// `(1, 0, 0, 1), #00ff00, rgb(0, 0, 255)` yields three colours.
+fn split_list(s: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(&s[start..]);
+
+    items
+        .into_iter()
+        .map(|item| {
+            let item = item.trim();
+            item.strip_prefix("(")
+                .and_then(|x| x.strip_suffix(")"))
+                .unwrap_or(item)
+        })
+        .collect()
+}
+
+// Each period is drawn as `fps * time` frames from t = 0 to t = 1, which
+// takes at least two of them.
+fn frame_step(fps: f32, time: f32) -> Result<f32> {
+    let frames = fps * time;
+    if !frames.is_finite() || frames < 2.0 {
+        return Err(anyhow!("Expected fps * time to be at least 2 frames, got {fps} * {time}"));
+    }
+    Ok(1.0 / (frames - 1.0))
+}
+
+// The outline is built from `samples` points, so it needs at least one.
+fn parse_samples(s: &str) -> Result<usize> {
+    let samples = s.trim().parse()?;
+    if samples == 0 {
+        return Err(anyhow!("Expected samples to be at least 1, got {s}"));
+    }
+    Ok(samples)
+}
+
+// `from` or `from, to`, where leaving out `to` grows the chain to every harmonic.
+fn parse_harmonic_range(s: &str) -> Result<(u32, Option<u32>)> {
+    let values = s.split(",").map(|x| Ok(x.trim().parse()?)).collect::<Result<Vec<u32>>>()?;
+    match values[..] {
+        [from] => Ok((from, None)),
+        [from, to] if from <= to => Ok((from, Some(to))),
+        _ => Err(anyhow!("Expected animate_harmonics to be from or from, to with from <= to, got {s}")),
+    }
+}
+
+// Resolves `solid`, `dashed` or `dotted` to dash and gap lengths, either of
+// which may be overridden.
+fn parse_dash(style: Option<&str>, length: Option<&str>, gap: Option<&str>) -> Result<Option<[f32; 2]>> {
+    let [default_length, default_gap] = match style.map(str::trim) {
+        None | Some("solid") => return Ok(None),
+        Some("dashed") => polyline::DASHED,
+        Some("dotted") => polyline::DOTTED,
+        Some(other) => return Err(anyhow!("Unknown line style {other}, expected solid, dashed or dotted")),
+    };
+
+    let length = length.map(|x| x.trim().parse()).transpose()?.unwrap_or(default_length);
+    let gap = gap.map(|x| x.trim().parse()).transpose()?.unwrap_or(default_gap);
+    if length <= 0.0 || gap <= 0.0 {
+        return Err(anyhow!("Expected positive dash lengths, got {length} and {gap}"));
+    }
+
+    Ok(Some([length, gap]))
+}
+
+// Reads the `[guides]` section. `label_scale` enlarges tick labels drawn into
+// a supersampled scene so they keep their size on screen.
+fn parse_guides<'a>(section: &HashMap<String, Option<String>>, facade: &impl Facade, program: Rc<Program>, label_scale: f32) -> Result<Guides<'a>> {
+    let (axes, axes_colour, axes_width, axes_style) =
+        get_optional!(section, "axes", "axes_colour", "axes_width", "axes_style");
+    let (tick_spacing, tick_labels, label_size, label_colour) =
+        get_optional!(section, "tick_spacing", "tick_labels", "label_size", "label_colour");
+    let (grid_spacing, grid_colour, grid_width, grid_style) =
+        get_optional!(section, "grid_spacing", "grid_colour", "grid_width", "grid_style");
+    let (unit_circle, circle_colour, circle_width, circle_style) =
+        get_optional!(section, "unit_circle", "unit_circle_colour", "unit_circle_width", "unit_circle_style");
+
+    let axes = match parse_bool("axes", axes, false)? {
+        true => {
+            let style = guide_style(axes_colour, axes_width, axes_style, Colour { r: 0.6, g: 0.6, b: 0.6, a: 0.8 })?;
+            let ticks = tick_spacing
+                .map(|spacing| {
+                    let labels = match parse_bool("tick_labels", tick_labels, true)? {
+                        true => Some((
+                            label_size.map(|x| x.trim().parse()).transpose()?.unwrap_or(12.0) * label_scale,
+                            label_colour.map(str::parse).transpose()?.unwrap_or(style.colour),
+                        )),
+                        false => None,
+                    };
+                    Ok(Ticks {
+                        spacing: spacing.trim().parse()?,
+                        labels,
+                    })
+                })
+                .transpose()?;
+            Some((style, ticks))
+        }
+        false if tick_spacing.is_some() => return Err(anyhow!("Expected axes = yes for tick_spacing")),
+        false => None,
+    };
+
+    let grid = grid_spacing
+        .map(|spacing| {
+            let style = guide_style(grid_colour, grid_width, grid_style, Colour { r: 0.4, g: 0.4, b: 0.4, a: 0.35 })?;
+            Ok((style, spacing.trim().parse()?))
+        })
+        .transpose()?;
+
+    let unit_circle = match parse_bool("unit_circle", unit_circle, false)? {
+        true => Some(guide_style(circle_colour, circle_width, circle_style, Colour { r: 0.6, g: 0.6, b: 0.6, a: 0.6 })?),
+        false => None,
+    };
+
+    Guides::new(facade, program, axes, grid, unit_circle)
+}
+
+fn guide_style(colour: Option<&str>, width: Option<&str>, style: Option<&str>, default: Colour) -> Result<LineStyle> {
+    Ok(LineStyle {
+        colour: colour.map(str::parse).transpose()?.unwrap_or(default),
+        width: width.map(|x| x.trim().parse()).transpose()?.unwrap_or(1.0),
+        alpha_mode: AlphaMode::Blend,
+        dash: parse_dash(style, None, None)?,
+    })
+}
+
+fn parse_colour_list(s: &str) -> Result<Vec<Colour>> {
+    split_list(s).into_iter().map(str::parse).collect()
+}
+
+// Compares runs of digits by value, so `line2` comes before `line10`.
+fn section_order(a: &str, b: &str) -> Ordering {
+    fn chunks(mut rest: &str) -> Vec<(Option<u64>, &str)> {
+        let mut chunks = vec![];
+        while let Some(c) = rest.chars().next() {
+            let digits = c.is_ascii_digit();
+            let end = rest.find(|c: char| c.is_ascii_digit() != digits).unwrap_or(rest.len());
+            let (chunk, tail) = rest.split_at(end);
+            chunks.push(match digits {
+                true => (chunk.parse().ok(), ""),
+                false => (None, chunk),
+            });
+            rest = tail;
+        }
+        chunks
+    }
+
+    chunks(a).cmp(&chunks(b)).then_with(|| a.cmp(b))
+}
+
+fn parse_gradient(s: &str) -> Result<Gradient> {
+    match Gradient::named(s) {
+        Some(gradient) => Ok(gradient),
+        None => Ok(Gradient::Stops(parse_colour_list(s)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colour_lists_accept_every_syntax() {
+        let colours = parse_colour_list("(1, 0, 0, 0.5), #00ff00, rgb(0, 0, 255), (hsl(0, 0%, 100%))").unwrap();
+        assert_eq!(
+            colours,
+            [
+                Colour { r: 1.0, g: 0.0, b: 0.0, a: 0.5 },
+                Colour { r: 0.0, g: 1.0, b: 0.0, a: 1.0 },
+                Colour { r: 0.0, g: 0.0, b: 1.0, a: 1.0 },
+                Colour { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn periods_need_two_frames() {
+        assert_eq!(frame_step(30.0, 2.0).unwrap(), 1.0 / 59.0);
+        assert_eq!(frame_step(2.0, 1.0).unwrap(), 1.0);
+        assert!(frame_step(1.0, 1.0).is_err());
+        assert!(frame_step(30.0, 0.0).is_err());
+        assert!(frame_step(-30.0, 2.0).is_err());
+    }
+
+    #[test]
+    fn samples_must_be_positive() {
+        assert_eq!(parse_samples(" 256").unwrap(), 256);
+        assert!(parse_samples("0").is_err());
+        assert!(parse_samples("-4").is_err());
+    }
+
+    #[test]
+    fn sections_are_ordered_by_number() {
+        let mut names = vec!["line10", "line2", "line1", "settings", "line1b", "line"];
+        names.sort_by(|a, b| section_order(a, b));
+        assert_eq!(names, ["line", "line1", "line1b", "line2", "line10", "settings"]);
+    }
+
+    #[test]
+    fn harmonic_ranges_take_an_optional_end() {
+        assert_eq!(parse_harmonic_range("2").unwrap(), (2, None));
+        assert_eq!(parse_harmonic_range(" 1, 12").unwrap(), (1, Some(12)));
+        assert!(parse_harmonic_range("5, 3").is_err());
+        assert!(parse_harmonic_range("1, 2, 3").is_err());
+    }
+
+    #[test]
+    fn dash_styles_have_defaults_and_overrides() {
+        assert_eq!(parse_dash(None, Some("0.1"), None).unwrap(), None);
+        assert_eq!(parse_dash(Some("solid"), None, None).unwrap(), None);
+        assert_eq!(parse_dash(Some("dashed"), None, None).unwrap(), Some(polyline::DASHED));
+        assert_eq!(parse_dash(Some("dotted"), None, Some("0.05")).unwrap(), Some([polyline::DOTTED[0], 0.05]));
+        assert!(parse_dash(Some("dashed"), Some("0"), None).is_err());
+        assert!(parse_dash(Some("wavy"), None, None).is_err());
+    }
+
+    #[test]
+    fn lines_take_exactly_one_source() {
+        let section = |pairs: &[(&str, &str)]| -> HashMap<String, Option<String>> {
+            pairs.iter().map(|(k, v)| (k.to_string(), Some(v.to_string()))).collect()
+        };
+        let dir = Path::new(".");
+
+        assert_eq!(parse_routes(&section(&[("points", "(0,1),(1,0)")]), dir).unwrap()[0].points.len(), 2);
+        assert_eq!(parse_routes(&section(&[("signal", "square"), ("harmonics", "4")]), dir).unwrap()[0].points.len(), 9);
+        assert_eq!(
+            parse_routes(&section(&[("samples_y", "0, 1, -1")]), dir).unwrap()[0].points,
+            [Complex32::new(0.0, 0.0), Complex32::new(0.0, 1.0), Complex32::new(0.0, -1.0)]
+        );
+
+        assert!(parse_routes(&section(&[]), dir).is_err());
+        assert!(parse_routes(&section(&[("signal", "square"), ("samples_y", "1, 2")]), dir).is_err());
+        assert!(parse_routes(&section(&[("signal", "square"), ("harmonics", "0")]), dir).is_err());
+
+        assert_eq!(parse_routes(&section(&[("shape", "heart")]), dir).unwrap()[0].points.len(), 256);
+        let expr = section(&[("shape", "expr"), ("x", "cos(t)"), ("y", "sin(t)"), ("points_count", "4")]);
+        assert!((parse_routes(&expr, dir).unwrap()[0].points[1] - Complex32::new(0.0, 1.0)).norm() < 1e-6);
+        assert!(parse_routes(&section(&[("shape", "expr"), ("x", "t")]), dir).is_err());
+
+        let z = parse_routes(&section(&[("z", "exp(i*t)"), ("points_count", "4")]), dir).unwrap();
+        assert!((z[0].points[1] - Complex32::new(0.0, 1.0)).norm() < 1e-6);
+        assert!(parse_routes(&section(&[("z", "t"), ("x", "t"), ("y", "t")]), dir).is_err());
+        assert!(parse_routes(&section(&[("shape", "heart"), ("z", "t")]), dir).is_err());
+        let error = parse_routes(&section(&[("z", "exp(j*t)")]), dir).unwrap_err().to_string();
+        assert!(error.starts_with("Unable to parse z: Unknown name j at position 4"), "{error}");
+        assert!(parse_routes(&section(&[("shape", "circle"), ("points", "(0,1)")]), dir).is_err());
+
+        let text = |join: &str| parse_routes(&section(&[("text", "\"Hi i\""), ("points_count", "64"), ("join", join)]), dir);
+        let glyphs = text("no").unwrap();
+        assert_eq!(glyphs.len(), 3);
+        // The i's stem and dot are one route, with the travel between them hidden.
+        assert_eq!(glyphs[1].points.len(), 64);
+        assert_eq!(glyphs[1].hidden.len(), 2);
+        assert_eq!(text("yes").unwrap()[0].hidden.len(), 5);
+        assert!(parse_routes(&section(&[("text", " ")]), dir).is_err());
+        assert!(parse_routes(&section(&[("text", "Hi"), ("font", "missing.ttf")]), dir).is_err());
+    }
+
+    #[test]
+    fn multisampling_must_be_a_power_of_two() {
+        let path = std::env::temp_dir().join("fourier_multisampling.ini");
+        let parse = |samples: &str| {
+            std::fs::write(&path, format!("[setup]\nmultisampling = {samples}\n")).unwrap();
+            WindowSetup::parse_ini(path.to_str().unwrap(), &[]).map(|setup| setup.multisampling)
+        };
+
+        assert_eq!(parse("4").unwrap(), 4);
+        assert_eq!(parse("0").unwrap(), 0);
+        assert!(parse("3").is_err());
+        assert!(parse("many").is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_window_keys() {
+        let path = std::env::temp_dir().join("fourier_window.ini");
+        std::fs::write(&path, "[setup]\nwidth = 640\ntitle = From ini\nvsync = yes\n").unwrap();
+
+        let args = ["--width", "1920", "--title=Overlay", "--fullscreen", "--vsync", "no"].map(String::from);
+        let setup = WindowSetup::parse_ini(path.to_str().unwrap(), &parse_args(args).unwrap()).unwrap();
+
+        assert_eq!((setup.width, setup.height), (1920, 720));
+        assert_eq!(setup.title, "Overlay");
+        assert!(setup.fullscreen && !setup.vsync && setup.resizable && !setup.transparent);
+
+        assert!(parse_args(["--colour".to_string()]).is_err());
+        assert!(parse_args(["--width".to_string()]).is_err());
+    }
 }
@@ -0,0 +1,257 @@
+use std::{ops::Range, rc::Rc};
+
+use anyhow::anyhow;
+use glium::{
+    backend::Facade,
+    draw_parameters::Smooth,
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    Blend, BlendingFunction, LinearBlendingFactor, Program, VertexBuffer,
+};
+
+use num::Complex;
+
+use crate::{
+    colour::Gradient,
+    graphics::{line_fragment_shader, DrawItem, Drawable, LineUniform},
+    Colour, Complex32,
+};
+
+#[derive(Copy, Clone, Default)]
+pub struct LineVertex {
+    pub position: [f32; 2],
+    pub alpha: f32,
+    pub param: f32,
+    // Arc length from the start of the line, which dash patterns are laid out along.
+    pub distance: f32,
+}
+
+implement_vertex!(LineVertex, position, alpha, param, distance);
+
+impl std::fmt::Display for LineVertex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.position[0], self.position[1])
+    }
+}
+
+impl From<LineVertex> for Complex32 {
+    fn from(value: LineVertex) -> Self {
+        Complex {
+            re: value.position[0],
+            im: value.position[1],
+        }
+    }
+}
+
+impl TryFrom<&str> for LineVertex {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        let mut v = value.split(",").map(|x| anyhow::Ok(x.trim().parse()?));
+        let err = "Unable to parse value as a vertex";
+        Ok(Self {
+            position: [
+                v.next().ok_or(anyhow!(err))??,
+                v.next().ok_or(anyhow!(err))??,
+            ],
+            alpha: 1.0,
+            param: 0.0,
+            distance: 0.0,
+        })
+    }
+}
+
+impl From<Complex32> for LineVertex {
+    fn from(value: Complex32) -> Self {
+        LineVertex {
+            position: [value.re, value.im],
+            alpha: 1.0,
+            param: 0.0,
+            distance: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlphaMode {
+    Blend,
+    // Overlapping lines add up towards white, which reads as a glow.
+    Additive,
+}
+
+impl AlphaMode {
+    pub fn blend(self) -> Blend {
+        match self {
+            AlphaMode::Blend => Blend::alpha_blending(),
+            AlphaMode::Additive => Blend {
+                color: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::SourceAlpha,
+                    destination: LinearBlendingFactor::One,
+                },
+                alpha: BlendingFunction::Addition {
+                    source: LinearBlendingFactor::One,
+                    destination: LinearBlendingFactor::One,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for AlphaMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "alpha" => Ok(AlphaMode::Blend),
+            "additive" => Ok(AlphaMode::Additive),
+            other => Err(anyhow!("Unknown blend mode {other}, expected alpha or additive")),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LineStyle {
+    pub colour: Colour,
+    pub width: f32,
+    pub alpha_mode: AlphaMode,
+    // Lengths of each dash and of the gap after it, or `None` for a solid line.
+    pub dash: Option<[f32; 2]>,
+}
+
+// Default dash lengths, in the same units as the normalised shapes.
+pub const DASHED: [f32; 2] = [0.05, 0.03];
+pub const DOTTED: [f32; 2] = [0.004, 0.02];
+
+pub struct Polyline<'a> {
+    draw_item: DrawItem<'a, LineVertex, LineUniform>,
+    width: f32,
+}
+
+impl<'a> Polyline<'a> {
+    // A polyline whose vertices are fixed at creation.
+    pub fn new(
+        name: &'static str,
+        facade: &impl Facade,
+        data: &[LineVertex],
+        program: Rc<Program>,
+        style: &LineStyle,
+        gradient: Option<&Gradient>,
+    ) -> Self {
+        Self::with_buffer(name, facade, VertexBuffer::new(facade, data).unwrap(), PrimitiveType::LineStrip, program, style, gradient)
+    }
+
+    // Separate segments, one for each pair of vertices, fixed at creation.
+    pub fn lines(
+        name: &'static str,
+        facade: &impl Facade,
+        data: &[LineVertex],
+        program: Rc<Program>,
+        style: &LineStyle,
+    ) -> Self {
+        Self::with_buffer(name, facade, VertexBuffer::new(facade, data).unwrap(), PrimitiveType::LinesList, program, style, None)
+    }
+
+    // A polyline of `len` vertices that are uploaded every frame.
+    pub fn dynamic(
+        name: &'static str,
+        facade: &impl Facade,
+        len: usize,
+        program: Rc<Program>,
+        style: &LineStyle,
+        gradient: Option<&Gradient>,
+    ) -> Self {
+        Self::with_buffer(name, facade, VertexBuffer::empty_dynamic(facade, len).unwrap(), PrimitiveType::LineStrip, program, style, gradient)
+    }
+
+    fn with_buffer(
+        name: &'static str,
+        facade: &impl Facade,
+        buffer: VertexBuffer<LineVertex>,
+        primitive: PrimitiveType,
+        program: Rc<Program>,
+        style: &LineStyle,
+        gradient: Option<&Gradient>,
+    ) -> Self {
+        let mut draw_item = DrawItem::new(
+            name,
+            buffer,
+            NoIndices(primitive),
+            program,
+            LineUniform::new(facade, style.colour, gradient),
+        );
+        draw_item.uniforms_mut().dash = style.dash.unwrap_or_default();
+        draw_item.params_mut().blend = style.alpha_mode.blend();
+        draw_item.params_mut().line_width = Some(style.width);
+
+        Self {
+            draw_item,
+            width: style.width,
+        }
+    }
+
+    pub fn set_gradient_offset(&mut self, offset: f32) {
+        self.draw_item.uniforms_mut().offset = offset;
+    }
+
+    pub fn set_scale(&mut self, scale: [f32; 2]) {
+        self.draw_item.uniforms_mut().scale = scale;
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>) {
+        self.draw_item.set_range(range);
+    }
+
+    // `width_scale` multiplies the style's width, for surfaces with more
+    // pixels than the screen.
+    pub fn set_line_hints(&mut self, smooth: Option<Smooth>, width_scale: f32) {
+        let params = self.draw_item.params_mut();
+        params.smooth = smooth;
+        params.line_width = Some(self.width * width_scale);
+    }
+}
+
+impl<'a> Drawable<'a, LineVertex, LineUniform> for Polyline<'a> {
+    fn upload(&mut self, data: &[LineVertex]) {
+        self.draw_item.upload(data);
+    }
+
+    fn draw<S: glium::Surface>(&self, surface: &mut S) -> Result<(), glium::DrawError> {
+        self.draw_item.draw(surface)
+    }
+}
+
+// With `flat`, each segment takes its `param` from its last vertex instead of
+// interpolating, so bars are coloured one by one.
+pub fn get_program(facade: &impl Facade, flat: bool) -> glium::Program {
+    let interpolation = if flat { "flat " } else { "" };
+
+    let vertex_shader_src = format!(
+        r#"
+            #version 140
+
+            uniform vec2 scale;
+
+            in vec2 position;
+            in float alpha;
+            in float param;
+            in float distance;
+
+            out float v_alpha;
+            {interpolation}out float v_param;
+            out float v_distance;
+
+            void main() {{
+                vec2 pos = position;
+                v_alpha = alpha;
+                v_param = param;
+                v_distance = distance;
+                gl_Position = vec4(pos * scale, 0.0, 1.0);
+            }}
+        "#
+    );
+
+    let fragment_shader_src = line_fragment_shader(flat);
+
+    glium::Program::from_source(facade, &vertex_shader_src, &fragment_shader_src, None).unwrap()
+}
@@ -0,0 +1,210 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Result};
+use glium::{
+    backend::{Context, Facade},
+    framebuffer::SimpleFrameBuffer,
+    texture::{MipmapsOption, RawImage2d, SrgbFormat, SrgbTexture2d},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter},
+    DrawParameters, Program, Rect, Surface,
+};
+
+use crate::graphics::{check_texture_size, draw_fullscreen, fullscreen_program, LINEAR_TO_SRGB};
+
+// A fixed size offscreen surface for renders, so the output resolution does
+// not depend on the window. Frames are saved from it as numbered PNGs, and the
+// window only shows a letterboxed preview.
+pub struct RenderTarget {
+    context: Rc<Context>,
+    texture: SrgbTexture2d,
+    preview: bool,
+    output: Option<PathBuf>,
+    frame: u32,
+    program: Program,
+}
+
+impl RenderTarget {
+    pub fn new(facade: &impl Facade, width: u32, height: u32, preview: bool, output: Option<PathBuf>) -> Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Expected a positive render size, got {width}x{height}"));
+        }
+        check_texture_size(facade, (width, height))?;
+        if !preview && output.is_none() {
+            return Err(anyhow!("Expected render_output to be set when render_preview is no"));
+        }
+        if let Some(dir) = &output {
+            fs::create_dir_all(dir).map_err(|e| anyhow!("Unable to create {}: {e}", dir.display()))?;
+        }
+
+        Ok(Self {
+            context: Rc::clone(facade.get_context()),
+            texture: SrgbTexture2d::empty_with_format(facade, SrgbFormat::U8U8U8U8, MipmapsOption::NoMipmap, width, height)?,
+            preview,
+            output,
+            frame: 0,
+            program: get_program(facade)?,
+        })
+    }
+
+    pub fn saves_frames(&self) -> bool {
+        self.output.is_some()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.texture.dimensions()
+    }
+
+    pub fn draw<S: Surface>(&mut self, window: &mut S, draw: impl FnOnce(&mut SimpleFrameBuffer) -> Result<()>) -> Result<()> {
+        draw(&mut SimpleFrameBuffer::new(&self.context, &self.texture)?)?;
+        self.save_frame()?;
+
+        window.clear_color(0.0, 0.0, 0.0, 1.0);
+        if !self.preview {
+            return Ok(());
+        }
+
+        let params = DrawParameters {
+            viewport: Some(letterbox(window.get_dimensions(), self.texture.dimensions())),
+            ..Default::default()
+        };
+        let sampler = self
+            .texture
+            .sampled()
+            .minify_filter(MinifySamplerFilter::Linear)
+            .magnify_filter(MagnifySamplerFilter::Linear);
+
+        draw_fullscreen(window, &self.program, &uniform! { scene: sampler }, &params)?;
+        Ok(())
+    }
+
+    // The texture holds sRGB bytes already, so they are written out as they are.
+    fn save_frame(&mut self) -> Result<()> {
+        let Some(dir) = &self.output else {
+            return Ok(());
+        };
+        let path = dir.join(format!("frame_{:05}.png", self.frame));
+        self.frame += 1;
+
+        let image: RawImage2d<u8> = self.texture.read();
+        let (width, height) = (image.width, image.height);
+        write_png(&path, width, height, &flip_rows(image))
+    }
+}
+
+// OpenGL returns rows bottom to top, while image files store them top to bottom.
+pub fn flip_rows(image: RawImage2d<u8>) -> Vec<u8> {
+    let row = image.width as usize * 4;
+    image.data.chunks_exact(row).rev().flatten().copied().collect()
+}
+
+// Writes 8-bit RGBA pixels, stored top row first, as a PNG.
+pub fn write_png(path: &Path, width: u32, height: u32, data: &[u8]) -> Result<()> {
+    let file = File::create(path).map_err(|e| anyhow!("Unable to create {}: {e}", path.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(data)?;
+    Ok(())
+}
+
+// The largest rectangle with the aspect ratio of `image` centred in `window`.
+fn letterbox((window_width, window_height): (u32, u32), (width, height): (u32, u32)) -> Rect {
+    let scale = f32::min(window_width as f32 / width as f32, window_height as f32 / height as f32);
+    let fitted_width = (width as f32 * scale).round() as u32;
+    let fitted_height = (height as f32 * scale).round() as u32;
+
+    Rect {
+        left: (window_width - fitted_width) / 2,
+        bottom: (window_height - fitted_height) / 2,
+        width: fitted_width,
+        height: fitted_height,
+    }
+}
+
+fn get_program(facade: &impl Facade) -> Result<Program> {
+    let fragment_shader_src = format!(
+        r#"
+            #version 140
+
+            uniform sampler2D scene;
+
+            in vec2 v_uv;
+            out vec4 color;
+            {LINEAR_TO_SRGB}
+            void main() {{
+                color = linear_to_srgb(texture(scene, v_uv));
+            }}
+        "#
+    );
+
+    Ok(fullscreen_program(facade, &fragment_shader_src)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless;
+    use glium::CapabilitiesSource;
+
+    #[test]
+    fn letterbox_keeps_the_aspect_ratio() {
+        let rect = letterbox((720, 720), (3840, 2160));
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (0, 157, 720, 405));
+
+        let rect = letterbox((1000, 500), (500, 500));
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (250, 0, 500, 500));
+    }
+
+    #[test]
+    fn frames_are_saved_without_a_preview() {
+        let facade = headless::context(1, 1);
+        let dir = std::env::temp_dir().join("fourier_render_output");
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(RenderTarget::new(&facade, 64, 32, false, None).is_err());
+        let mut target = RenderTarget::new(&facade, 64, 32, false, Some(dir.clone())).unwrap();
+        let mut window = glium::Frame::new(facade.clone(), (1, 1));
+        for _ in 0..2 {
+            target
+                .draw(&mut window, |surface| {
+                    surface.clear_color(1.0, 0.0, 0.0, 1.0);
+                    Ok(())
+                })
+                .unwrap();
+        }
+        window.finish().unwrap();
+
+        let decoder = png::Decoder::new(File::open(dir.join("frame_00001.png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (64, 32));
+        assert_eq!(data[..4], [255, 0, 0, 255]);
+        assert!(!dir.join("frame_00002.png").exists());
+    }
+
+    #[test]
+    fn sizes_past_the_texture_limit_are_rejected() {
+        let facade = headless::context(1, 1);
+        let max = facade.get_context().get_capabilities().max_texture_size as u32;
+        assert!(RenderTarget::new(&facade, max + 1, 16, true, None).is_err());
+    }
+
+    #[test]
+    fn failed_saves_are_returned() {
+        let facade = headless::context(1, 1);
+        let dir = std::env::temp_dir().join("fourier_render_output_removed");
+
+        let mut target = RenderTarget::new(&facade, 8, 8, false, Some(dir.clone())).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let mut window = glium::Frame::new(facade.clone(), (1, 1));
+        assert!(target.draw(&mut window, |_| Ok(())).is_err());
+        window.finish().unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use anyhow::{anyhow, Result};
+
+use crate::{expr::Expression, Complex32};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle,
+    Polygon(u32),
+    // Points, then the inner radius relative to the outer one.
+    Star(u32, f32),
+    Heart,
+    Lissajous(u32, u32),
+    Rose(u32),
+    // Fixed radius, rolling radius and pen distance.
+    Epitrochoid(u32, u32, f32),
+    // x(t) and y(t), of which only the real parts are used.
+    Cartesian(Expression, Expression),
+    // z(t) in the complex plane.
+    Parametric(Expression),
+}
+
+// `name` or `name:arg,arg`. Expressions come from other keys, so those curves
+// are built directly rather than parsed.
+impl std::str::FromStr for Shape {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = s.trim().split_once(":").unwrap_or((s.trim(), ""));
+        let args: Vec<&str> = args.split(",").map(str::trim).filter(|x| !x.is_empty()).collect();
+        let arg = |i: usize, default: Option<&'static str>| -> Result<&str> {
+            args.get(i)
+                .copied()
+                .or(default)
+                .ok_or(anyhow!("Expected {name} to have at least {} arguments, got {s}", i + 1))
+        };
+
+        let shape = match name {
+            "circle" => Shape::Circle,
+            "polygon" => Shape::Polygon(arg(0, None)?.parse()?),
+            "star" => Shape::Star(arg(0, Some("5"))?.parse()?, arg(1, Some("0.4"))?.parse()?),
+            "heart" => Shape::Heart,
+            "lissajous" => Shape::Lissajous(arg(0, Some("3"))?.parse()?, arg(1, Some("2"))?.parse()?),
+            "rose" => Shape::Rose(arg(0, Some("4"))?.parse()?),
+            "epitrochoid" => Shape::Epitrochoid(
+                arg(0, Some("3"))?.parse()?,
+                arg(1, Some("1"))?.parse()?,
+                arg(2, Some("2"))?.parse()?,
+            ),
+            other => {
+                return Err(anyhow!(
+                    "Unknown shape {other}, expected circle, polygon, star, heart, lissajous, rose, epitrochoid or expr"
+                ))
+            }
+        };
+
+        let arity = match shape {
+            Shape::Polygon(_) | Shape::Rose(_) => 1,
+            Shape::Star(..) | Shape::Lissajous(..) => 2,
+            Shape::Epitrochoid(..) => 3,
+            _ => 0,
+        };
+        if args.len() > arity {
+            return Err(anyhow!("Expected {name} to have at most {arity} arguments, got {s}"));
+        }
+
+        let expected = match shape {
+            Shape::Polygon(n) | Shape::Star(n, _) => n >= 3,
+            Shape::Lissajous(a, b) => a > 0 && b > 0,
+            Shape::Rose(k) => k > 0,
+            Shape::Epitrochoid(fixed, rolling, _) => fixed > 0 && rolling > 0,
+            _ => true,
+        };
+        if !expected {
+            return Err(anyhow!("Invalid arguments for shape {s}"));
+        }
+        Ok(shape)
+    }
+}
+
+impl Shape {
+    // `count` points evenly spaced in the curve parameter over one period.
+    pub fn points(&self, count: usize) -> Result<Vec<Complex32>> {
+        match self {
+            Shape::Polygon(n) => Ok(corners(&regular(*n as usize, |_| 1.0), count)),
+            Shape::Star(n, inner) => Ok(corners(&regular(2 * *n as usize, |i| if i % 2 == 0 { 1.0 } else { *inner }), count)),
+            _ => (0..count)
+                .map(|i| self.finite_point(i as f32 / count as f32))
+                .collect(),
+        }
+    }
+
+    // Expressions such as 1/t or ln(t) blow up at some t, and a single
+    // infinite or NaN point would spread through every coefficient.
+    fn finite_point(&self, s: f32) -> Result<Complex32> {
+        let point = self.point(s);
+        let (key, value) = match self {
+            Shape::Cartesian(..) if !point.re.is_finite() => ("x", point.re.to_string()),
+            Shape::Cartesian(..) if !point.im.is_finite() => ("y", point.im.to_string()),
+            Shape::Parametric(_) if !point.is_finite() => ("z", point.to_string()),
+            _ => return Ok(point),
+        };
+        Err(anyhow!("Expected {key} to be finite, got {value} at t = {}", TAU * s))
+    }
+
+    // The point at `s` in 0..1 along a curve.
+    fn point(&self, s: f32) -> Complex32 {
+        let t = TAU * s;
+        match self {
+            Shape::Heart => Complex32::new(
+                16.0 * t.sin().powi(3),
+                13.0 * t.cos() - 5.0 * (2.0 * t).cos() - 2.0 * (3.0 * t).cos() - (4.0 * t).cos(),
+            ),
+            Shape::Lissajous(a, b) => Complex32::new((*a as f32 * t + FRAC_PI_2).sin(), (*b as f32 * t).sin()),
+            // Odd roses retrace themselves after half a turn.
+            Shape::Rose(k) => {
+                let t = if k % 2 == 1 { t / 2.0 } else { t };
+                Complex32::from_polar((*k as f32 * t).cos(), t)
+            }
+            // The curve closes once the rolling circle has come back round to
+            // the same point of the fixed one.
+            Shape::Epitrochoid(fixed, rolling, pen) => {
+                let t = t * (rolling / gcd(*fixed, *rolling)) as f32;
+                let (r, ratio) = ((fixed + rolling) as f32, (fixed + rolling) as f32 / *rolling as f32);
+                Complex32::from_polar(r, t) - Complex32::from_polar(*pen, ratio * t)
+            }
+            Shape::Cartesian(x, y) => Complex32::new(x.eval(t as f64).re as f32, y.eval(t as f64).re as f32),
+            Shape::Parametric(z) => {
+                let z = z.eval(t as f64);
+                Complex32::new(z.re as f32, z.im as f32)
+            }
+            Shape::Circle | Shape::Polygon(_) | Shape::Star(..) => Complex32::from_polar(1.0, t),
+        }
+    }
+}
+
+// Corners of a regular polygon starting at the top, with radius given per corner.
+fn regular(n: usize, radius: impl Fn(usize) -> f32) -> Vec<Complex32> {
+    (0..n)
+        .map(|i| Complex32::from_polar(radius(i), FRAC_PI_2 + TAU * i as f32 / n as f32))
+        .collect()
+}
+
+// Spaces points evenly by edge around the closed path through `corners`.
+fn corners(corners: &[Complex32], count: usize) -> Vec<Complex32> {
+    (0..count)
+        .map(|i| {
+            let s = i as f32 / count as f32 * corners.len() as f32;
+            let edge = s as usize;
+            let a = corners[edge];
+            let b = corners[(edge + 1) % corners.len()];
+            a + (b - a) * s.fract()
+        })
+        .collect()
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Complex32, b: Complex32) -> bool {
+        (a - b).norm() < 1e-5
+    }
+
+    #[test]
+    fn shapes_parse_with_default_arguments() {
+        assert_eq!("polygon:5".parse::<Shape>().unwrap(), Shape::Polygon(5));
+        assert_eq!("star".parse::<Shape>().unwrap(), Shape::Star(5, 0.4));
+        assert_eq!(" star:6, 0.5".parse::<Shape>().unwrap(), Shape::Star(6, 0.5));
+        assert_eq!("epitrochoid:5".parse::<Shape>().unwrap(), Shape::Epitrochoid(5, 1, 2.0));
+
+        assert!("polygon".parse::<Shape>().is_err());
+        assert!("polygon:2".parse::<Shape>().is_err());
+        assert!("rose:0".parse::<Shape>().is_err());
+        assert!("polygon:5,1,2".parse::<Shape>().is_err());
+        assert!("circle:7".parse::<Shape>().is_err());
+        assert!("blob".parse::<Shape>().is_err());
+    }
+
+    #[test]
+    fn polygons_walk_their_edges() {
+        let square = Shape::Polygon(4).points(8).unwrap();
+        assert_eq!(square.len(), 8);
+        assert!(close(square[0], Complex32::new(0.0, 1.0)));
+        assert!(close(square[1], Complex32::new(-0.5, 0.5)));
+        assert!(close(square[2], Complex32::new(-1.0, 0.0)));
+
+        let star = Shape::Star(5, 0.4).points(10).unwrap();
+        assert!(close(star[1], Complex32::from_polar(0.4, FRAC_PI_2 + TAU / 10.0)));
+    }
+
+    #[test]
+    fn curves_close_after_one_period() {
+        let shapes = [
+            Shape::Circle,
+            Shape::Heart,
+            Shape::Lissajous(3, 2),
+            Shape::Rose(3),
+            Shape::Rose(4),
+            Shape::Epitrochoid(5, 3, 5.0),
+            Shape::Cartesian("cos(t)".parse().unwrap(), "sin(2*t)".parse().unwrap()),
+            Shape::Parametric("exp(i*t) + 0.3*exp(-3*i*t)".parse().unwrap()),
+        ];
+
+        for shape in shapes {
+            assert!(close(shape.point(0.0), shape.point(1.0)), "{shape:?}");
+            // Nowhere near the start halfway round.
+            assert!(!close(shape.point(0.0), shape.point(0.37)), "{shape:?}");
+        }
+    }
+
+    #[test]
+    fn non_finite_points_name_their_key() {
+        let error = |shape: Shape| shape.points(8).unwrap_err().to_string();
+
+        let x = error(Shape::Cartesian("1/t".parse().unwrap(), "t".parse().unwrap()));
+        assert!(x.starts_with("Expected x to be finite") && x.ends_with("at t = 0"), "{x}");
+        let y = error(Shape::Cartesian("t".parse().unwrap(), "ln(t)".parse().unwrap()));
+        assert!(y.starts_with("Expected y to be finite, got -inf at t = 0"), "{y}");
+        let z = error(Shape::Parametric("exp(i*t) / t".parse().unwrap()));
+        assert!(z.starts_with("Expected z to be finite") && z.ends_with("at t = 0"), "{z}");
+    }
+}
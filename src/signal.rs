@@ -0,0 +1,98 @@
+use std::f32::consts::{PI, TAU};
+
+use anyhow::{anyhow, Result};
+
+use crate::Complex32;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+}
+
+impl std::str::FromStr for Waveform {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "sine" => Ok(Waveform::Sine),
+            "square" => Ok(Waveform::Square),
+            "sawtooth" => Ok(Waveform::Sawtooth),
+            "triangle" => Ok(Waveform::Triangle),
+            other => Err(anyhow!("Unknown signal {other}, expected sine, square, sawtooth or triangle")),
+        }
+    }
+}
+
+impl Waveform {
+    // The sine coefficient b_n of the wave's real Fourier series over one
+    // period; all of these waves are odd, so every a_n is zero.
+    fn sine_coefficient(self, n: u32) -> f32 {
+        let n_f = n as f32;
+        match self {
+            Waveform::Sine if n == 1 => 1.0,
+            Waveform::Square if n % 2 == 1 => 4.0 / (PI * n_f),
+            Waveform::Sawtooth => -2.0 / (PI * n_f),
+            Waveform::Triangle if n % 2 == 1 => {
+                let sign = if n % 4 == 1 { 1.0 } else { -1.0 };
+                sign * 8.0 / (PI * PI * n_f * n_f)
+            }
+            Waveform::Sine | Waveform::Square | Waveform::Triangle => 0.0,
+        }
+    }
+
+    // The partial sum up to `harmonics`, sampled at exactly enough points for
+    // the transform to recover each coefficient without aliasing.
+    pub fn points(self, harmonics: u32) -> Vec<Complex32> {
+        let count = 2 * harmonics as usize + 1;
+        let coefficients: Vec<f32> = (1..=harmonics).map(|n| self.sine_coefficient(n)).collect();
+
+        let samples: Vec<f32> = (0..count)
+            .map(|k| {
+                let t = k as f32 / count as f32;
+                coefficients
+                    .iter()
+                    .zip(1..)
+                    .map(|(b, n)| b * (TAU * n as f32 * t).sin())
+                    .sum()
+            })
+            .collect();
+        signal_points(&samples)
+    }
+}
+
+// A real signal is drawn along the imaginary axis, so the pen moves up and
+// down and its imaginary projection traces out the waveform.
+pub fn signal_points(samples: &[f32]) -> Vec<Complex32> {
+    samples.iter().map(|y| Complex32::new(0.0, *y)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_wave_has_odd_harmonics_only() {
+        let square = Waveform::Square;
+        assert!((square.sine_coefficient(1) - 4.0 / PI).abs() < 1e-6);
+        assert_eq!(square.sine_coefficient(2), 0.0);
+        assert!((square.sine_coefficient(3) - 4.0 / (3.0 * PI)).abs() < 1e-6);
+
+        assert!(Waveform::Triangle.sine_coefficient(3) < 0.0);
+        assert_eq!(Waveform::Sine.sine_coefficient(2), 0.0);
+        assert!("wobble".parse::<Waveform>().is_err());
+    }
+
+    #[test]
+    fn points_follow_the_waveform_on_the_imaginary_axis() {
+        let points = Waveform::Square.points(40);
+        assert_eq!(points.len(), 81);
+        assert!(points.iter().all(|p| p.re == 0.0));
+
+        // Away from the jumps the partial sum is close to +1 then -1.
+        assert!((points[20].im - 1.0).abs() < 0.05);
+        assert!((points[60].im + 1.0).abs() < 0.05);
+    }
+}
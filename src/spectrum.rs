@@ -0,0 +1,259 @@
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use glium::{
+    backend::Facade,
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    uniforms::EmptyUniforms,
+    Program, Rect, Surface, VertexBuffer,
+};
+
+use crate::{
+    graphics::{DrawItem, Drawable},
+    Colour, Complex32,
+};
+
+// Margin around the panel as a fraction of the surface height.
+const MARGIN: f32 = 0.02;
+// Fraction of each harmonic's slot taken up by its bar.
+const BAR_FILL: f32 = 0.7;
+// Orders of magnitude shown on a logarithmic scale.
+const LOG_DECADES: f32 = 4.0;
+
+#[derive(Copy, Clone)]
+pub struct SpectrumVertex {
+    pub position: [f32; 2],
+    pub colour: [f32; 4],
+}
+
+implement_vertex!(SpectrumVertex, position, colour);
+
+impl std::fmt::Display for SpectrumVertex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.position[0], self.position[1])
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl std::str::FromStr for Corner {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "top_left" => Ok(Corner::TopLeft),
+            "top_right" => Ok(Corner::TopRight),
+            "bottom_left" => Ok(Corner::BottomLeft),
+            "bottom_right" => Ok(Corner::BottomRight),
+            other => Err(anyhow!("Unknown corner {other}, expected top_left, top_right, bottom_left or bottom_right")),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SpectrumStyle {
+    pub corner: Corner,
+    // Panel height as a fraction of the surface height.
+    pub size: f32,
+    pub background: Colour,
+    pub colour: Colour,
+    pub highlight: Colour,
+    pub log_scale: bool,
+    // Harmonics from -harmonics to harmonics are plotted.
+    pub harmonics: u32,
+}
+
+// An inset bar chart of |c_n| against n, with the harmonics currently in the
+// epicycle chain highlighted.
+pub struct Spectrum<'a> {
+    draw_item: DrawItem<'a, SpectrumVertex, EmptyUniforms>,
+    style: SpectrumStyle,
+    // Bar height in 0..1 for each n from -harmonics to harmonics.
+    heights: Vec<f32>,
+    shown: Option<u32>,
+}
+
+impl<'a> Spectrum<'a> {
+    pub fn new(facade: &impl Facade, bars: &[(i32, Complex32)], program: Rc<Program>, style: SpectrumStyle) -> Self {
+        let heights = heights(bars, style.harmonics, style.log_scale);
+        // A background quad and then one quad per harmonic.
+        let len = 6 * (heights.len() + 1);
+
+        Self {
+            draw_item: DrawItem::new(
+                "Spectrum",
+                VertexBuffer::empty_dynamic(facade, len).unwrap(),
+                NoIndices(PrimitiveType::TrianglesList),
+                program,
+                EmptyUniforms,
+            ),
+            style,
+            heights,
+            shown: None,
+        }
+    }
+
+    // Highlights harmonics up to `shown`, re-uploading only when it changes.
+    pub fn set_shown(&mut self, shown: u32) {
+        if self.shown == Some(shown) {
+            return;
+        }
+        self.shown = Some(shown);
+
+        let harmonics = self.style.harmonics as i32;
+        let slot = 2.0 / self.heights.len() as f32;
+        let mut vertices = quad([-1.0, -1.0], [1.0, 1.0], self.style.background);
+
+        for (i, height) in self.heights.iter().enumerate() {
+            let n = i as i32 - harmonics;
+            let colour = match n.unsigned_abs() <= shown {
+                true => self.style.highlight,
+                false => self.style.colour,
+            };
+            let centre = -1.0 + (i as f32 + 0.5) * slot;
+            let half = 0.5 * slot * BAR_FILL;
+            vertices.extend(quad([centre - half, -0.9], [centre + half, -0.9 + 1.8 * height], colour));
+        }
+
+        self.draw_item.upload(&vertices);
+    }
+
+    #[cfg(test)]
+    pub fn shown(&self) -> Option<u32> {
+        self.shown
+    }
+
+    pub fn draw<S: Surface>(&mut self, surface: &mut S) {
+        let viewport = panel(surface.get_dimensions(), &self.style);
+        self.draw_item.params_mut().viewport = Some(viewport);
+        self.draw_item.draw(surface).unwrap();
+    }
+}
+
+fn quad(min: [f32; 2], max: [f32; 2], colour: Colour) -> Vec<SpectrumVertex> {
+    let colour = colour.into();
+    [[min[0], min[1]], [max[0], min[1]], [min[0], max[1]], [min[0], max[1]], [max[0], min[1]], [max[0], max[1]]]
+        .map(|position| SpectrumVertex { position, colour })
+        .to_vec()
+}
+
+// Scales |c_n| for n from -harmonics to harmonics so the largest is 1.
+fn heights(bars: &[(i32, Complex32)], harmonics: u32, log_scale: bool) -> Vec<f32> {
+    let harmonics = harmonics as i32;
+    let mut norms = vec![0.0; 2 * harmonics as usize + 1];
+    for (n, coef) in bars {
+        if n.abs() <= harmonics {
+            norms[(n + harmonics) as usize] = coef.norm();
+        }
+    }
+
+    let max = norms.iter().copied().fold(0.0, f32::max).max(f32::EPSILON);
+    norms
+        .into_iter()
+        .map(|norm| match log_scale {
+            true if norm > 0.0 => (1.0 + (norm / max).log10() / LOG_DECADES).max(0.0),
+            true => 0.0,
+            false => norm / max,
+        })
+        .collect()
+}
+
+// The panel's rectangle in pixels, at most twice as wide as it is tall.
+fn panel((width, height): (u32, u32), style: &SpectrumStyle) -> Rect {
+    let margin = (MARGIN * height as f32).round() as u32;
+    let panel_height = ((style.size * height as f32).round() as u32).min(height.saturating_sub(2 * margin));
+    let panel_width = (2 * panel_height).min(width.saturating_sub(2 * margin));
+
+    let left = match style.corner {
+        Corner::TopLeft | Corner::BottomLeft => margin,
+        Corner::TopRight | Corner::BottomRight => width.saturating_sub(margin + panel_width),
+    };
+    let bottom = match style.corner {
+        Corner::BottomLeft | Corner::BottomRight => margin,
+        Corner::TopLeft | Corner::TopRight => height.saturating_sub(margin + panel_height),
+    };
+
+    Rect {
+        left,
+        bottom,
+        width: panel_width,
+        height: panel_height,
+    }
+}
+
+pub fn get_program(facade: &impl Facade) -> Program {
+    let vertex_shader_src = r#"
+            #version 140
+
+            in vec2 position;
+            in vec4 colour;
+
+            out vec4 v_colour;
+
+            void main() {
+                v_colour = colour;
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        "#;
+
+    let fragment_shader_src = r#"
+            #version 140
+
+            in vec4 v_colour;
+            out vec4 color;
+
+            void main() {
+                color = v_colour;
+            }
+        "#;
+
+    Program::from_source(facade, vertex_shader_src, fragment_shader_src, None).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heights_are_relative_to_the_largest_coefficient() {
+        let bars = [
+            (0, Complex32::new(0.0, 0.0)),
+            (1, Complex32::new(0.0, 2.0)),
+            (-1, Complex32::new(0.2, 0.0)),
+            (2, Complex32::new(1.0, 0.0)),
+        ];
+
+        assert_eq!(heights(&bars, 1, false), [0.1, 0.0, 1.0]);
+        let log = heights(&bars, 2, true);
+        assert_eq!(log.len(), 5);
+        assert_eq!(log[0], 0.0);
+        assert!((log[1] - 0.75).abs() < 1e-6);
+        assert_eq!(log[3], 1.0);
+    }
+
+    #[test]
+    fn panel_sits_in_its_corner() {
+        let style = SpectrumStyle {
+            corner: Corner::TopRight,
+            size: 0.25,
+            background: Colour { r: 0.0, g: 0.0, b: 0.0, a: 0.5 },
+            colour: Colour { r: 0.5, g: 0.5, b: 0.5, a: 1.0 },
+            highlight: Colour { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+            log_scale: false,
+            harmonics: 8,
+        };
+
+        let rect = panel((800, 400), &style);
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (592, 292, 200, 100));
+
+        let rect = panel((100, 400), &SpectrumStyle { corner: Corner::BottomLeft, ..style });
+        assert_eq!((rect.left, rect.bottom, rect.width, rect.height), (8, 8, 84, 100));
+    }
+}
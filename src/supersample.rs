@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use glium::{
+    backend::{Context, Facade},
+    framebuffer::SimpleFrameBuffer,
+    texture::{MipmapsOption, SrgbFormat, SrgbTexture2d},
+    uniform, Program, Surface,
+};
+
+use crate::graphics::{check_texture_size, draw_fullscreen, fullscreen_program, LINEAR_TO_SRGB};
+
+// Renders offscreen at `factor` times the target resolution, then box filters
+// each factor x factor block down to one pixel. The scene texture is sRGB, so
+// the average is taken in linear light and edges do not darken.
+pub struct Supersampler {
+    context: Rc<Context>,
+    factor: u32,
+    scene: Option<SrgbTexture2d>,
+    program: Program,
+}
+
+impl Supersampler {
+    pub fn new(facade: &impl Facade, factor: u32) -> Result<Self> {
+        if factor < 2 {
+            return Err(anyhow!("Expected supersampling of at least 2, got {factor}"));
+        }
+
+        Ok(Self {
+            context: Rc::clone(facade.get_context()),
+            factor,
+            scene: None,
+            program: get_program(facade)?,
+        })
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    // Sizes the scene texture for a target of `width` by `height`.
+    pub fn resize(&mut self, (width, height): (u32, u32)) -> Result<()> {
+        let size = (width.saturating_mul(self.factor), height.saturating_mul(self.factor));
+        if self.scene.as_ref().map(SrgbTexture2d::dimensions) != Some(size) {
+            check_texture_size(&self.context, size)
+                .map_err(|e| anyhow!("Unable to supersample {width}x{height} by {}: {e}", self.factor))?;
+            self.scene = Some(SrgbTexture2d::empty_with_format(
+                &self.context,
+                SrgbFormat::U8U8U8U8,
+                MipmapsOption::NoMipmap,
+                size.0,
+                size.1,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn draw<S: Surface>(&mut self, target: &mut S, draw: impl FnOnce(&mut SimpleFrameBuffer)) -> Result<()> {
+        self.resize(target.get_dimensions())?;
+        let scene = self.scene.as_ref().unwrap();
+
+        draw(&mut SimpleFrameBuffer::new(&self.context, scene)?);
+
+        draw_fullscreen(
+            target,
+            &self.program,
+            &uniform! { scene: scene, factor: self.factor as i32 },
+            &Default::default(),
+        )?;
+        Ok(())
+    }
+}
+
+fn get_program(facade: &impl Facade) -> Result<Program> {
+    let fragment_shader_src = format!(
+        r#"
+            #version 140
+
+            uniform sampler2D scene;
+            uniform int factor;
+
+            out vec4 color;
+            {LINEAR_TO_SRGB}
+            void main() {{
+                ivec2 base = ivec2(gl_FragCoord.xy) * factor;
+                vec4 sum = vec4(0.0);
+
+                for (int y = 0; y < factor; y++) {{
+                    for (int x = 0; x < factor; x++) {{
+                        sum += texelFetch(scene, base + ivec2(x, y), 0);
+                    }}
+                }}
+
+                color = linear_to_srgb(sum / float(factor * factor));
+            }}
+        "#
+    );
+
+    Ok(fullscreen_program(facade, &fragment_shader_src)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless;
+    use glium::CapabilitiesSource;
+
+    #[test]
+    fn scenes_past_the_texture_limit_are_rejected() {
+        let facade = headless::context(1, 1);
+        let max = facade.get_context().get_capabilities().max_texture_size as u32;
+        let mut supersampler = Supersampler::new(&facade, 4).unwrap();
+
+        assert!(supersampler.resize((max / 2, 16)).is_err());
+        assert!(supersampler.resize((16, 16)).is_ok());
+    }
+}
@@ -0,0 +1,244 @@
+use std::{borrow::Cow, collections::HashMap, rc::Rc};
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use anyhow::{anyhow, Result};
+use glium::{
+    backend::{Context, Facade},
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    texture::{ClientFormat, MipmapsOption, RawImage2d, Texture2d, UncompressedFloatFormat},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter},
+    Blend, DrawParameters, Program, Surface, VertexBuffer,
+};
+
+use crate::Colour;
+
+pub const FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+const ATLAS_WIDTH: u32 = 512;
+// Characters outside the atlas are drawn as this one.
+const FALLBACK: char = '?';
+
+#[derive(Copy, Clone)]
+struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+}
+
+implement_vertex!(TextVertex, position, uv);
+
+#[derive(Copy, Clone)]
+struct Glyph {
+    // Offset of the bitmap from the pen, which sits at the top of the line.
+    offset: [f32; 2],
+    size: [f32; 2],
+    // Top left corner of the bitmap in the atlas, in texels.
+    atlas: [f32; 2],
+    advance: f32,
+}
+
+// Every printable ASCII character rasterised once into a single channel texture.
+struct GlyphAtlas {
+    texture: Texture2d,
+    glyphs: HashMap<char, Glyph>,
+    line_height: f32,
+}
+
+impl GlyphAtlas {
+    fn new(facade: &impl Facade, size: f32) -> Result<Self> {
+        let font = FontRef::try_from_slice(FONT)?;
+        let scaled = font.as_scaled(PxScale::from(size));
+
+        let mut bitmaps = vec![];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        let mut glyphs = HashMap::new();
+
+        for c in (' '..='~').filter(|c| !c.is_control()) {
+            let id = font.glyph_id(c);
+            let advance = scaled.h_advance(id);
+            let outlined = font.outline_glyph(id.with_scale_and_position(scaled.scale(), point(0.0, scaled.ascent())));
+
+            let Some(outlined) = outlined else {
+                glyphs.insert(c, Glyph { offset: [0.0; 2], size: [0.0; 2], atlas: [0.0; 2], advance });
+                continue;
+            };
+
+            let bounds = outlined.px_bounds();
+            let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+            // One texel of padding keeps neighbours out of linear samples.
+            if x + width + 1 > ATLAS_WIDTH {
+                (x, y, row_height) = (0, y + row_height + 1, 0);
+            }
+
+            let mut bitmap = vec![0; (width * height) as usize];
+            outlined.draw(|gx, gy, coverage| bitmap[(gy * width + gx) as usize] = (coverage * 255.0).round() as u8);
+            bitmaps.push((x, y, width, bitmap));
+
+            glyphs.insert(
+                c,
+                Glyph {
+                    offset: [bounds.min.x, bounds.min.y],
+                    size: [width as f32, height as f32],
+                    atlas: [x as f32, y as f32],
+                    advance,
+                },
+            );
+            x += width + 1;
+            row_height = row_height.max(height);
+        }
+
+        let height = (y + row_height).max(1);
+        let mut data = vec![0; (ATLAS_WIDTH * height) as usize];
+        for (x, y, width, bitmap) in bitmaps {
+            for (row, pixels) in bitmap.chunks_exact(width.max(1) as usize).enumerate() {
+                let start = ((y + row as u32) * ATLAS_WIDTH + x) as usize;
+                data[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+        }
+
+        let image = RawImage2d {
+            data: Cow::Owned(data),
+            width: ATLAS_WIDTH,
+            height,
+            format: ClientFormat::U8,
+        };
+
+        Ok(Self {
+            texture: Texture2d::with_format(facade, image, UncompressedFloatFormat::U8, MipmapsOption::NoMipmap)?,
+            glyphs,
+            line_height: (scaled.height() + scaled.line_gap()).ceil(),
+        })
+    }
+
+    fn glyph(&self, c: char) -> &Glyph {
+        self.glyphs.get(&c).unwrap_or(&self.glyphs[&FALLBACK])
+    }
+}
+
+// Draws text in pixel coordinates from the top left of a surface, using the
+// font embedded in the binary so the output is the same on every machine.
+pub struct TextRenderer {
+    context: Rc<Context>,
+    atlas: GlyphAtlas,
+    program: Program,
+}
+
+impl TextRenderer {
+    pub fn new(facade: &impl Facade, size: f32) -> Result<Self> {
+        if size <= 0.0 {
+            return Err(anyhow!("Expected a positive text size, got {size}"));
+        }
+
+        Ok(Self {
+            context: Rc::clone(facade.get_context()),
+            atlas: GlyphAtlas::new(facade, size)?,
+            program: get_program(facade)?,
+        })
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.atlas.line_height
+    }
+
+    // Width of the longest line of `text`, in pixels.
+    pub fn width(&self, text: &str) -> f32 {
+        text.lines()
+            .map(|line| line.chars().map(|c| self.atlas.glyph(c).advance).sum())
+            .fold(0.0, f32::max)
+    }
+
+    // `position` is the top left corner of the first line; each `\n` starts a new line.
+    pub fn draw<S: Surface>(&self, surface: &mut S, text: &str, position: [f32; 2], colour: Colour) -> Result<()> {
+        let (width, height) = self.atlas.texture.dimensions();
+        let texel = [1.0 / width as f32, 1.0 / height as f32];
+
+        let mut vertices = vec![];
+        for (row, line) in text.lines().enumerate() {
+            let mut pen = [position[0].round(), (position[1] + row as f32 * self.atlas.line_height).round()];
+
+            for c in line.chars() {
+                let glyph = self.atlas.glyph(c);
+                let [x, y] = [pen[0] + glyph.offset[0], pen[1] + glyph.offset[1]];
+                let [w, h] = glyph.size;
+                let [u, v] = [glyph.atlas[0] * texel[0], glyph.atlas[1] * texel[1]];
+                let [du, dv] = [w * texel[0], h * texel[1]];
+
+                let corners = [
+                    ([x, y], [u, v]),
+                    ([x + w, y], [u + du, v]),
+                    ([x, y + h], [u, v + dv]),
+                    ([x + w, y + h], [u + du, v + dv]),
+                ];
+                vertices.extend([0, 1, 2, 2, 1, 3].map(|i| TextVertex {
+                    position: corners[i].0,
+                    uv: corners[i].1,
+                }));
+                pen[0] += glyph.advance;
+            }
+        }
+
+        if vertices.is_empty() {
+            return Ok(());
+        }
+
+        let (surface_width, surface_height) = surface.get_dimensions();
+        let sampler = self
+            .atlas
+            .texture
+            .sampled()
+            .minify_filter(MinifySamplerFilter::Nearest)
+            .magnify_filter(MagnifySamplerFilter::Nearest);
+        let uniforms = uniform! {
+            atlas: sampler,
+            text_colour: colour,
+            viewport: [surface_width as f32, surface_height as f32],
+        };
+        let params = DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+
+        surface.draw(
+            &VertexBuffer::new(&self.context, &vertices)?,
+            NoIndices(PrimitiveType::TrianglesList),
+            &self.program,
+            &uniforms,
+            &params,
+        )?;
+        Ok(())
+    }
+}
+
+fn get_program(facade: &impl Facade) -> Result<Program> {
+    let vertex_shader_src = r#"
+            #version 140
+
+            uniform vec2 viewport;
+
+            in vec2 position;
+            in vec2 uv;
+
+            out vec2 v_uv;
+
+            void main() {
+                v_uv = uv;
+                gl_Position = vec4(position.x / viewport.x * 2.0 - 1.0, 1.0 - position.y / viewport.y * 2.0, 0.0, 1.0);
+            }
+        "#;
+
+    let fragment_shader_src = r#"
+            #version 140
+
+            uniform sampler2D atlas;
+            uniform vec4 text_colour;
+
+            in vec2 v_uv;
+            out vec4 color;
+
+            void main() {
+                color = vec4(text_colour.rgb, text_colour.a * texture(atlas, v_uv).r);
+            }
+        "#;
+
+    Ok(Program::from_source(facade, vertex_shader_src, fragment_shader_src, None)?)
+}
@@ -0,0 +1,205 @@
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use glium::{backend::Facade, draw_parameters::Smooth, Program, Rect, Surface};
+
+use crate::{
+    graphics::{aspect_scale_of, Drawable},
+    polyline::{LineStyle, Polyline},
+    Colour, Complex32, LineVertex,
+};
+
+// Points along each trace, however much of the period it covers.
+const TRACE_POINTS: usize = 512;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Strips {
+    Both,
+    Re,
+    Im,
+}
+
+// Splits the scene into the canvas and up to two strips: Im(z) scrolls to the
+// right of the canvas and Re(z) scrolls below it.
+#[derive(Copy, Clone, Debug)]
+pub struct TraceLayout {
+    pub strips: Strips,
+    // Fraction of the scene's width and height taken by the strips.
+    pub size: f32,
+    // Periods of history shown by each trace.
+    pub length: f32,
+}
+
+pub struct Regions {
+    pub canvas: Rect,
+    right: Option<Rect>,
+    bottom: Option<Rect>,
+}
+
+impl TraceLayout {
+    pub fn new(strips: Strips, size: f32, length: f32) -> Result<Self> {
+        if !(0.0..1.0).contains(&size) || length <= 0.0 {
+            return Err(anyhow!("Expected a trace_size in 0..1 and a positive trace_length, got {size} and {length}"));
+        }
+        Ok(Self { strips, size, length })
+    }
+
+    pub fn regions(&self, (width, height): (u32, u32)) -> Regions {
+        let (im, re) = (self.strips != Strips::Re, self.strips != Strips::Im);
+        let strip_width = if im { (width as f32 * self.size).round() as u32 } else { 0 };
+        let strip_height = if re { (height as f32 * self.size).round() as u32 } else { 0 };
+        let canvas = Rect {
+            left: 0,
+            bottom: strip_height,
+            width: width - strip_width,
+            height: height - strip_height,
+        };
+
+        Regions {
+            canvas,
+            right: im.then_some(Rect {
+                left: canvas.width,
+                width: strip_width,
+                ..canvas
+            }),
+            bottom: re.then_some(Rect {
+                bottom: 0,
+                height: strip_height,
+                ..canvas
+            }),
+        }
+    }
+}
+
+impl Regions {
+    // Maps a point of the plane to pixels of the whole scene, as the canvas draws it.
+    fn canvas_pixel(&self, z: Complex32) -> [f32; 2] {
+        let canvas = self.canvas;
+        let scale = aspect_scale_of((canvas.width, canvas.height));
+        [
+            canvas.left as f32 + (z.re * scale[0] + 1.0) * 0.5 * canvas.width as f32,
+            canvas.bottom as f32 + (z.im * scale[1] + 1.0) * 0.5 * canvas.height as f32,
+        ]
+    }
+}
+
+// The pen's real and imaginary parts plotted against time, with guides from
+// the pen to where each trace begins.
+pub struct ProjectionTraces<'a> {
+    re: Polyline<'a>,
+    im: Polyline<'a>,
+    guide: Polyline<'a>,
+    length: f32,
+    t: f32,
+    // Periods of history since the animation started, until a full loop has played.
+    history: f32,
+}
+
+impl<'a> ProjectionTraces<'a> {
+    pub fn new(facade: &impl Facade, program: Rc<Program>, length: f32, style: &LineStyle) -> Self {
+        let guide_style = LineStyle {
+            colour: Colour { a: style.colour.a * 0.4, ..style.colour },
+            width: 1.0,
+            dash: None,
+            ..*style
+        };
+
+        Self {
+            re: Polyline::dynamic("Re trace", facade, TRACE_POINTS, Rc::clone(&program), style, None),
+            im: Polyline::dynamic("Im trace", facade, TRACE_POINTS, Rc::clone(&program), style, None),
+            guide: Polyline::dynamic("Trace guide", facade, 3, program, &guide_style, None),
+            length,
+            t: 0.0,
+            history: 0.0,
+        }
+    }
+
+    pub fn set_line_hints(&mut self, smooth: Option<Smooth>, width_scale: f32) {
+        for line in [&mut self.re, &mut self.im, &mut self.guide] {
+            line.set_line_hints(smooth, width_scale);
+        }
+    }
+
+    // `looped` is whether the outline has been drawn in full at least once,
+    // before which the traces only reach back to the start.
+    pub fn set_time(&mut self, t: f32, looped: bool) {
+        self.t = t;
+        self.history = if looped { self.length } else { t.min(self.length) };
+    }
+
+    // `outline` is z sampled evenly over one period.
+    pub fn draw<S: Surface>(&mut self, surface: &mut S, regions: &Regions, outline: &[LineVertex]) {
+        if outline.is_empty() {
+            return;
+        }
+        let (width, height) = surface.get_dimensions();
+        let ndc = |[x, y]: [f32; 2]| LineVertex::from(Complex32::new(x / width as f32 * 2.0 - 1.0, y / height as f32 * 2.0 - 1.0));
+
+        // Age runs from 0 at the pen to 1 at the far end of each strip.
+        let samples: Vec<(f32, [f32; 2])> = (0..TRACE_POINTS)
+            .map(|i| {
+                let age = i as f32 / (TRACE_POINTS - 1) as f32;
+                let s = (self.t - age * self.length).rem_euclid(1.0);
+                let index = (s * outline.len() as f32).round() as usize % outline.len();
+                (age, regions.canvas_pixel(outline[index].into()))
+            })
+            .collect();
+
+        // Without one of the strips the guide ends at the pen instead.
+        let [x, y] = samples[0].1;
+        let mut guide = [ndc([x, y]); 3];
+        let shown = ((self.history / self.length * (TRACE_POINTS - 1) as f32).ceil() as usize + 1).min(TRACE_POINTS);
+
+        if let Some(right) = regions.right {
+            let im: Vec<_> = samples
+                .iter()
+                .map(|(age, [_, y])| ndc([right.left as f32 + age * right.width as f32, *y]))
+                .collect();
+            guide[0] = ndc([right.left as f32, y]);
+            draw_trace(&mut self.im, surface, &im, shown);
+        }
+
+        if let Some(bottom) = regions.bottom {
+            let top = (bottom.bottom + bottom.height) as f32;
+            let re: Vec<_> = samples
+                .iter()
+                .map(|(age, [x, _])| ndc([*x, top - age * bottom.height as f32]))
+                .collect();
+            guide[2] = ndc([x, top]);
+            draw_trace(&mut self.re, surface, &re, shown);
+        }
+
+        self.guide.upload(&guide);
+        self.guide.draw(surface).unwrap();
+    }
+}
+
+fn draw_trace<S: Surface>(line: &mut Polyline, surface: &mut S, vertices: &[LineVertex], shown: usize) {
+    line.upload(vertices);
+    line.set_range(0..shown);
+    line.draw(surface).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_border_the_canvas() {
+        let regions = TraceLayout::new(Strips::Both, 0.25, 1.0).unwrap().regions((800, 400));
+        let rect = |r: Rect| (r.left, r.bottom, r.width, r.height);
+
+        assert_eq!(rect(regions.canvas), (0, 100, 600, 300));
+        assert_eq!(regions.right.map(rect), Some((600, 100, 200, 300)));
+        assert_eq!(regions.bottom.map(rect), Some((0, 0, 600, 100)));
+
+        // The origin is at the centre of the canvas.
+        assert_eq!(regions.canvas_pixel(Complex32::new(0.0, 0.0)), [300.0, 250.0]);
+        assert!(TraceLayout::new(Strips::Both, 1.0, 1.0).is_err());
+
+        let regions = TraceLayout::new(Strips::Im, 0.25, 1.0).unwrap().regions((800, 400));
+        assert_eq!(rect(regions.canvas), (0, 0, 600, 400));
+        assert_eq!(regions.right.map(rect), Some((600, 0, 200, 400)));
+        assert!(regions.bottom.is_none());
+    }
+}
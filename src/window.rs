@@ -0,0 +1,80 @@
+use std::num::NonZeroU32;
+
+use anyhow::{anyhow, Result};
+use glium::{
+    backend::glutin::Display,
+    glutin::{
+        config::{ConfigTemplateBuilder, GlConfig},
+        context::{ContextAttributesBuilder, NotCurrentGlContext},
+        display::{GetGlDisplay, GlDisplay},
+        surface::{GlSurface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
+    },
+    winit::{
+        dpi::PhysicalSize,
+        event_loop::EventLoop,
+        window::{Fullscreen, Window},
+    },
+};
+use glutin_winit::DisplayBuilder;
+use raw_window_handle::HasWindowHandle;
+
+use crate::parsing::WindowSetup;
+
+// Mirrors `SimpleWindowBuilder::build`, which hides the surface and so
+// cannot set the swap interval for vsync.
+pub fn build(event_loop: &EventLoop<()>, setup: &WindowSetup) -> Result<(Window, Display<WindowSurface>)> {
+    let attributes = Window::default_attributes()
+        .with_title(&setup.title)
+        .with_inner_size(PhysicalSize::new(setup.width, setup.height))
+        .with_resizable(setup.resizable)
+        .with_transparent(setup.transparent)
+        .with_fullscreen(setup.fullscreen.then_some(Fullscreen::Borderless(None)));
+
+    let mut template = ConfigTemplateBuilder::new().with_transparency(setup.transparent);
+    if setup.transparent {
+        template = template.with_alpha_size(8);
+    }
+    if setup.multisampling > 0 {
+        template = template.with_multisampling(setup.multisampling);
+    }
+
+    let transparent = setup.transparent;
+    let (window, config) = DisplayBuilder::new()
+        .with_window_attributes(Some(attributes))
+        .build(event_loop, template, |configs| {
+            // Prefer a config the compositor can blend when transparency is requested.
+            configs
+                .reduce(|best, config| {
+                    match transparent && config.supports_transparency() == Some(true) && best.supports_transparency() != Some(true) {
+                        true => config,
+                        false => best,
+                    }
+                })
+                .unwrap()
+        })
+        .map_err(|e| anyhow!("Unable to create a window: {e}"))?;
+    let window = window.ok_or(anyhow!("Unable to create a window"))?;
+
+    let (width, height): (u32, u32) = window.inner_size().into();
+    let handle = window.window_handle()?.as_raw();
+    let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+        handle,
+        NonZeroU32::new(width).ok_or(anyhow!("Window width must be positive"))?,
+        NonZeroU32::new(height).ok_or(anyhow!("Window height must be positive"))?,
+    );
+
+    let display = config.display();
+    let surface = unsafe { display.create_window_surface(&config, &surface_attributes) }?;
+    let context = unsafe { display.create_context(&config, &ContextAttributesBuilder::new().build(Some(handle))) }?
+        .make_current(&surface)?;
+
+    let interval = match setup.vsync {
+        true => SwapInterval::Wait(NonZeroU32::MIN),
+        false => SwapInterval::DontWait,
+    };
+    if let Err(e) = surface.set_swap_interval(&context, interval) {
+        eprintln!("Unable to set vsync to {}: {e}", setup.vsync);
+    }
+
+    Ok((window, Display::from_context_surface(context, surface)?))
+}